@@ -0,0 +1,39 @@
+//! Compares how long it takes to start an evaluator and evaluate a trivial
+//! expression with and without the V8 startup snapshot.
+//!
+//! Run with `cargo bench --bench startup`.
+
+use std::time::{Duration, Instant};
+
+use rix::eval::error::NixError;
+use rix::eval::execution::Evaluator;
+
+fn main() {
+    // `cargo bench` passes `--bench`. Otherwise (e.g. in `cargo test`) only
+    // check that the benchmark runs.
+    let iterations = if std::env::args().any(|arg| arg == "--bench") {
+        50
+    } else {
+        1
+    };
+    bench("startup with snapshot", iterations, Evaluator::new);
+    bench(
+        "startup without snapshot",
+        iterations,
+        Evaluator::new_without_snapshot,
+    );
+}
+
+fn bench(name: &str, iterations: u32, new_evaluator: fn() -> Result<Evaluator, NixError>) {
+    let workdir = std::env::current_dir().unwrap();
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let mut evaluator = new_evaluator().expect("Failed to create the evaluator.");
+        evaluator
+            .evaluate("1", &workdir)
+            .expect("Failed to evaluate.");
+        total += start.elapsed();
+    }
+    println!("{name}: {:?} per iteration", total / iterations);
+}
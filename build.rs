@@ -0,0 +1,106 @@
+//! Creates a V8 startup snapshot with the Nix runtime (`nixjs-rt`) already
+//! loaded, so that evaluators don't have to execute the runtime on start-up.
+//! The snapshot is only usable on the machine that created it, so it is left
+//! empty when cross-compiling or when `RIX_NO_SNAPSHOT` is set, and evaluators
+//! then load the runtime from its source instead.
+//!
+//! Also hashes everything that determines the transpiled JavaScript into
+//! `RIX_BUILD_HASH`, which keys the evaluation cache: the runtime, the
+//! evaluator's sources, the version of `rix` and the locked dependencies, such
+//! as the version of `rnix`.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use deno_core::v8;
+use sha2::{Digest, Sha256};
+
+#[path = "src/eval/nixjs_rt.rs"]
+#[allow(dead_code)]
+mod nixjs_rt;
+
+const NIXJS_RT_PATH: &str = "nixjs-rt/dist/lib.mjs";
+
+/// The sources of the evaluator, including the JavaScript emitter.
+const EVAL_SRC_DIR: &str = "src/eval";
+
+const CARGO_LOCK_PATH: &str = "Cargo.lock";
+
+fn main() {
+    println!("cargo:rerun-if-changed={NIXJS_RT_PATH}");
+    println!("cargo:rerun-if-changed={EVAL_SRC_DIR}");
+    println!("cargo:rerun-if-changed={CARGO_LOCK_PATH}");
+    println!("cargo:rerun-if-env-changed=RIX_NO_SNAPSHOT");
+
+    let nixjs_rt_src = std::fs::read_to_string(NIXJS_RT_PATH).unwrap_or_else(|err| {
+        panic!(
+            "Failed to read '{NIXJS_RT_PATH}'. Did you build nixjs-rt? See CONTRIBUTE.md. \
+             Error: {err}"
+        )
+    });
+
+    let cross_compiling = std::env::var("TARGET").unwrap() != std::env::var("HOST").unwrap();
+    let snapshot = if cross_compiling || std::env::var_os("RIX_NO_SNAPSHOT").is_some() {
+        Vec::new()
+    } else {
+        create_snapshot(&nixjs_rt_src)
+    };
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("nixjs_rt.snap"), snapshot)
+        .expect("Failed to write the V8 startup snapshot.");
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update(&nixjs_rt_src);
+    // Cargo.lock is missing when `rix` is built as a dependency.
+    hasher.update(std::fs::read(CARGO_LOCK_PATH).unwrap_or_default());
+    hash_dir(&mut hasher, Path::new(EVAL_SRC_DIR));
+    let build_hash = hasher
+        .finalize()
+        .iter()
+        .fold(String::with_capacity(64), |mut hash, byte| {
+            let _ = write!(hash, "{byte:02x}");
+            hash
+        });
+    println!("cargo:rustc-env=RIX_BUILD_HASH={build_hash}");
+}
+
+/// Creates a V8 startup snapshot whose default context has the Nix runtime
+/// loaded.
+fn create_snapshot(nixjs_rt_src: &str) -> Vec<u8> {
+    deno_core::JsRuntime::init_platform(None);
+    let mut isolate = v8::Isolate::snapshot_creator(None, None);
+    {
+        let scope = &mut v8::HandleScope::new(&mut isolate);
+        let context = v8::Context::new(scope);
+        let scope = &mut v8::ContextScope::new(scope, context);
+        nixjs_rt::load_nixjs_rt(scope, nixjs_rt_src).expect("Failed to load the Nix runtime.");
+        scope.set_default_context(context);
+    }
+    isolate
+        .create_blob(v8::FunctionCodeHandling::Keep)
+        .expect("Failed to create the V8 startup snapshot.")
+        .to_vec()
+}
+
+/// Hashes the paths and contents of the files in the directory, in a stable
+/// order.
+fn hash_dir(hasher: &mut Sha256, dir: &Path) {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("Failed to read '{}'. Error: {err}", dir.display()))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            hash_dir(hasher, &path);
+        } else {
+            hasher.update(path.to_string_lossy().as_bytes());
+            hasher.update(
+                std::fs::read(&path).unwrap_or_else(|err| {
+                    panic!("Failed to read '{}'. Error: {err}", path.display())
+                }),
+            );
+        }
+    }
+}
@@ -1,10 +1,12 @@
-import { err, errType, errTypes, highlighted } from "./errors";
+import { NixError, err, errType, errTypes, highlighted } from "./errors";
 import { abortError } from "./errors/abort";
+import { missingAttributeError } from "./errors/attribute";
+import { NixAssertionFailedError } from "./errors/assertion";
+import { NixImportFailedError } from "./errors/import";
 import { otherError } from "./errors/other";
 import { typeMismatchError } from "./errors/typeError";
 import {
   Attrset,
-  EvalCtx,
   FALSE,
   Lambda,
   NULL,
@@ -17,10 +19,11 @@ import {
   NixType,
   NixTypeClass,
   Path,
+  StrictAttrset,
   TRUE,
   nixBoolFromJs,
 } from "./lib";
-import { dirOf, isAbsolutePath, normalizePath } from "./utils";
+import { isAbsolutePath, normalizePath } from "./utils";
 
 type BuiltinsRecord = Record<string, (param: NixType) => NixType>;
 
@@ -40,6 +43,55 @@ function builtinBasicTypeMismatchError(
   );
 }
 
+/**
+ * Whether `builtins.tryEval` catches the error. Like in Nix, failed
+ * assertions are catchable, but aborts and type errors are not.
+ */
+function isCatchableError(error: unknown): boolean {
+  return (
+    error instanceof NixError &&
+    (error.kind instanceof NixAssertionFailedError ||
+      error.kind instanceof NixImportFailedError)
+  );
+}
+
+/**
+ * Returns the path given to a builtin either as a path or as a string.
+ */
+function pathArgument(fnName: string, arg: NixType): string {
+  const argStrict = arg.toStrict();
+  if (argStrict instanceof Path || argStrict instanceof NixString) {
+    return argStrict.toJs();
+  }
+  throw builtinBasicTypeMismatchError(fnName, argStrict, [Path, NixString]);
+}
+
+/**
+ * Returns the string given to a builtin.
+ */
+function stringArgument(fnName: string, arg: NixType): NixString {
+  const argStrict = arg.toStrict();
+  if (!(argStrict instanceof NixString)) {
+    throw builtinBasicTypeMismatchError(fnName, argStrict, NixString);
+  }
+  return argStrict;
+}
+
+/**
+ * Returns the string attribute of an attrset passed to a builtin.
+ */
+function stringAttr(fnName: string, attrset: Attrset, name: string): string {
+  const value = attrset.lookup(name);
+  if (value === undefined) {
+    throw missingAttributeError([name]);
+  }
+  const valueStrict = value.toStrict();
+  if (!(valueStrict instanceof NixString)) {
+    throw builtinBasicTypeMismatchError(fnName, valueStrict, NixString);
+  }
+  return valueStrict.value;
+}
+
 export function getBuiltins() {
   // Builtins are sorted by the order they appear in the Nix manual
   // https://nixos.org/manual/nix/stable/language/builtins.html
@@ -211,7 +263,17 @@ export function getBuiltins() {
     },
 
     convertHash: (arg) => {
-      throw new Error("unimplemented");
+      const argStrict = arg.toStrict();
+      if (!(argStrict instanceof Attrset)) {
+        throw builtinBasicTypeMismatchError("convertHash", argStrict, Attrset);
+      }
+      const hash = stringAttr("convertHash", argStrict, "hash");
+      const toHashFormat = stringAttr("convertHash", argStrict, "toHashFormat");
+      const hashAlgo =
+        argStrict.lookup("hashAlgo") === undefined
+          ? undefined
+          : stringAttr("convertHash", argStrict, "hashAlgo");
+      return new NixString(convertHash(hash, hashAlgo, toHashFormat));
     },
 
     deepSeq: (arg) => {
@@ -303,11 +365,25 @@ export function getBuiltins() {
     },
 
     getContext: (arg) => {
-      throw new Error("unimplemented");
+      const str = stringArgument("getContext", arg);
+      return new StrictAttrset(
+        new Map(
+          Array.from(str.context)
+            .sort()
+            .map((path) => [
+              path,
+              new StrictAttrset(new Map([["path", TRUE]])),
+            ]),
+        ),
+      );
     },
 
     getEnv: (arg) => {
-      throw new Error("unimplemented");
+      const name = arg.toStrict();
+      if (!(name instanceof NixString)) {
+        throw builtinBasicTypeMismatchError("getEnv", name, NixString);
+      }
+      return new NixString(getEnv(name.value));
     },
 
     getFlake: (arg) => {
@@ -323,15 +399,38 @@ export function getBuiltins() {
     },
 
     hasContext: (arg) => {
-      throw new Error("unimplemented");
+      return nixBoolFromJs(stringArgument("hasContext", arg).context.size > 0);
     },
 
-    hashFile: (arg) => {
-      throw new Error("unimplemented");
+    hashFile: (algo) => {
+      return new Lambda((path) => {
+        const algoStrict = algo.toStrict();
+        if (!(algoStrict instanceof NixString)) {
+          throw builtinBasicTypeMismatchError("hashFile", algoStrict, NixString);
+        }
+        const pathValue = pathArgument("hashFile", path);
+        return new NixString(hashFile(algoStrict.value, pathValue, "base16"));
+      });
     },
 
-    hashString: (arg) => {
-      throw new Error("unimplemented");
+    hashString: (algo) => {
+      return new Lambda((str) => {
+        const algoStrict = algo.toStrict();
+        if (!(algoStrict instanceof NixString)) {
+          throw builtinBasicTypeMismatchError(
+            "hashString",
+            algoStrict,
+            NixString,
+          );
+        }
+        const strStrict = str.toStrict();
+        if (!(strStrict instanceof NixString)) {
+          throw builtinBasicTypeMismatchError("hashString", strStrict, NixString);
+        }
+        return new NixString(
+          hashString(algoStrict.value, strStrict.value, "base16"),
+        );
+      });
     },
 
     head: (list) => {
@@ -376,10 +475,7 @@ export function getBuiltins() {
         );
       }
 
-      const resultingFn = importNixModule(pathValue);
-
-      const newCtx = new EvalCtx(dirOf(pathValue));
-      return resultingFn(newCtx);
+      return importNixModule(pathValue);
     },
 
     intersectAttrs: (arg) => {
@@ -387,39 +483,39 @@ export function getBuiltins() {
     },
 
     isAttrs: (arg) => {
-      return nixBoolFromJs(arg instanceof Attrset);
+      return nixBoolFromJs(arg.toStrict() instanceof Attrset);
     },
 
     isBool: (arg) => {
-      return nixBoolFromJs(arg instanceof NixBool);
+      return nixBoolFromJs(arg.toStrict() instanceof NixBool);
     },
 
     isFloat: (arg) => {
-      return nixBoolFromJs(arg instanceof NixFloat);
+      return nixBoolFromJs(arg.toStrict() instanceof NixFloat);
     },
 
     isFunction: (arg) => {
-      return nixBoolFromJs(arg instanceof Lambda);
+      return nixBoolFromJs(arg.toStrict() instanceof Lambda);
     },
 
     isInt: (arg) => {
-      return nixBoolFromJs(arg instanceof NixInt);
+      return nixBoolFromJs(arg.toStrict() instanceof NixInt);
     },
 
     isList: (arg) => {
-      return nixBoolFromJs(arg instanceof NixList);
+      return nixBoolFromJs(arg.toStrict() instanceof NixList);
     },
 
     isNull: (arg) => {
-      return nixBoolFromJs(arg instanceof NixNull);
+      return nixBoolFromJs(arg.toStrict() instanceof NixNull);
     },
 
     isPath: (arg) => {
-      return nixBoolFromJs(arg instanceof Path);
+      return nixBoolFromJs(arg.toStrict() instanceof Path);
     },
 
     isString: (arg) => {
-      return nixBoolFromJs(arg instanceof NixString);
+      return nixBoolFromJs(arg.toStrict() instanceof NixString);
     },
 
     length: (arg) => {
@@ -471,7 +567,7 @@ export function getBuiltins() {
     },
 
     pathExists: (arg) => {
-      throw new Error("unimplemented");
+      return nixBoolFromJs(pathExists(pathArgument("pathExists", arg)));
     },
 
     placeholder: (arg) => {
@@ -479,15 +575,23 @@ export function getBuiltins() {
     },
 
     readDir: (arg) => {
-      throw new Error("unimplemented");
+      const entries = readDir(pathArgument("readDir", arg));
+      return new StrictAttrset(
+        new Map(
+          entries.map(([name, fileType]) => [
+            name,
+            new NixString(fileType),
+          ]),
+        ),
+      );
     },
 
     readFile: (arg) => {
-      throw new Error("unimplemented");
+      return new NixString(readFile(pathArgument("readFile", arg)));
     },
 
     readFileType: (arg) => {
-      throw new Error("unimplemented");
+      return new NixString(readFileType(pathArgument("readFileType", arg)));
     },
 
     removeAttrs: (arg) => {
@@ -551,10 +655,11 @@ export function getBuiltins() {
     },
 
     toString: (arg: NixType) => {
-      if (arg instanceof NixString) {
-        return arg;
-      } else if (arg instanceof Path) {
-        return new NixString(arg.path);
+      const argStrict = arg.toStrict();
+      if (argStrict instanceof NixString) {
+        return argStrict;
+      } else if (argStrict instanceof Path) {
+        return new NixString(argStrict.path);
       }
 
       // TODO: Expand on this
@@ -574,7 +679,24 @@ export function getBuiltins() {
     },
 
     tryEval: (arg) => {
-      throw new Error("unimplemented");
+      try {
+        return new StrictAttrset(
+          new Map([
+            ["success", TRUE],
+            ["value", arg.toStrict()],
+          ]),
+        );
+      } catch (error) {
+        if (!isCatchableError(error)) {
+          throw error;
+        }
+        return new StrictAttrset(
+          new Map([
+            ["success", FALSE],
+            ["value", FALSE],
+          ]),
+        );
+      }
     },
 
     typeOf: (arg) => {
@@ -585,6 +707,10 @@ export function getBuiltins() {
       throw new Error("unimplemented");
     },
 
+    unsafeDiscardStringContext: (arg) => {
+      return new NixString(stringArgument("unsafeDiscardStringContext", arg).value);
+    },
+
     zipAttrsWith: (arg) => {
       throw new Error("unimplemented");
     },
@@ -0,0 +1,14 @@
+import { ErrorMessage, err, NixError, highlighted } from ".";
+
+export class NixAssertionFailedError {
+  constructor(public readonly condition: string) {}
+
+  toDefaultErrorMessage(): ErrorMessage {
+    return err`Assertion '${highlighted(this.condition)}' failed`;
+  }
+}
+
+export function assertionFailedError(condition: string) {
+  let error = new NixAssertionFailedError(condition);
+  return new NixError(error, error.toDefaultErrorMessage());
+}
@@ -0,0 +1,17 @@
+import { ErrorMessage, err, NixError, highlighted } from ".";
+
+export class NixImportFailedError {
+  constructor(
+    public readonly path: string,
+    public readonly reason: string,
+  ) {}
+
+  toDefaultErrorMessage(): ErrorMessage {
+    return err`Failed to import ${highlighted(this.path)}: ${this.reason}`;
+  }
+}
+
+export function importFailedError(path: string, reason: string) {
+  let error = new NixImportFailedError(path, reason);
+  return new NixError(error, error.toDefaultErrorMessage());
+}
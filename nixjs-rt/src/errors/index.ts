@@ -14,13 +14,18 @@ import {
   Path,
 } from "../lib";
 import { NixAbortError } from "./abort";
+import { NixAssertionFailedError } from "./assertion";
 import {
   NixAttributeAlreadyDefinedError,
   NixMissingAttributeError,
 } from "./attribute";
 import { ErrorMessage } from "./errorMessage";
 import { NixFunctionCallWithoutArgumentError } from "./function";
+import { NixImportFailedError } from "./import";
+import { NixIoError } from "./io";
 import { NixOtherError } from "./other";
+import { NixParseError } from "./parse";
+import { NixAccessForbiddenError } from "./restricted";
 import { NixTypeMismatchError } from "./typeError";
 import { NixCouldntFindVariableError } from "./variable";
 
@@ -29,11 +34,16 @@ export * from "./errorMessage";
 type NixErrorKind =
   | NixTypeMismatchError
   | NixAbortError
+  | NixAssertionFailedError
   | NixOtherError
   | NixMissingAttributeError
   | NixAttributeAlreadyDefinedError
   | NixFunctionCallWithoutArgumentError
-  | NixCouldntFindVariableError;
+  | NixCouldntFindVariableError
+  | NixImportFailedError
+  | NixIoError
+  | NixAccessForbiddenError
+  | NixParseError;
 
 /** The base error class. This class gets parsed in rix by Rust code. */
 export class NixError extends Error {
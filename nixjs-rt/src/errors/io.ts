@@ -0,0 +1,17 @@
+import { ErrorMessage, err, NixError, highlighted } from ".";
+
+export class NixIoError {
+  constructor(
+    public readonly path: string,
+    public readonly message: string,
+  ) {}
+
+  toDefaultErrorMessage(): ErrorMessage {
+    return err`Failed to access ${highlighted(this.path)}: ${this.message}`;
+  }
+}
+
+export function ioError(path: string, message: string) {
+  let error = new NixIoError(path, message);
+  return new NixError(error, error.toDefaultErrorMessage());
+}
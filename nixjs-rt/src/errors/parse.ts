@@ -0,0 +1,24 @@
+import { ErrorMessage, err, NixError } from ".";
+
+export class NixParseError {
+  constructor(
+    public readonly file: string,
+    public readonly line: number,
+    public readonly column: number,
+    public readonly message: string,
+  ) {}
+
+  toDefaultErrorMessage(): ErrorMessage {
+    return err`${this.message}`;
+  }
+}
+
+export function parseError(
+  file: string,
+  line: number,
+  column: number,
+  message: string,
+) {
+  let error = new NixParseError(file, line, column, message);
+  return new NixError(error, error.toDefaultErrorMessage());
+}
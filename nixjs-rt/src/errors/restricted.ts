@@ -0,0 +1,17 @@
+import { ErrorMessage, err, NixError, highlighted } from ".";
+
+export class NixAccessForbiddenError {
+  constructor(
+    public readonly path: string,
+    public readonly mode: string,
+  ) {}
+
+  toDefaultErrorMessage(): ErrorMessage {
+    return err`access to path ${highlighted(this.path)} is forbidden in ${this.mode}`;
+  }
+}
+
+export function accessForbiddenError(path: string, mode: string) {
+  let error = new NixAccessForbiddenError(path, mode);
+  return new NixError(error, error.toDefaultErrorMessage());
+}
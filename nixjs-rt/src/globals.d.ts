@@ -1,12 +1,81 @@
-import type { NixType, EvalCtx } from "./lib";
+import type { NixType } from "./lib";
 
 declare global {
   /**
    * Import a Nix module from the given path. The path is absolute.
-   * Returns a transpiled version of the module, executed, with a function
-   * that takes an EvalCtx and returns the module's value.
+   * Returns the value of the module. The host evaluates every file at most
+   * once per evaluation and returns the same value for later imports.
    */
-  var importNixModule: (path: string) => (ctx: EvalCtx) => NixType;
+  var importNixModule: (path: string) => NixType;
+
+  /**
+   * Read the contents of the file at the given path. Relative paths are
+   * resolved against the working directory of the evaluation. Throws a
+   * `NixIoError` if the file can't be read.
+   */
+  var readFile: (path: string) => string;
+
+  /**
+   * List the entries of the directory at the given path as pairs of their
+   * names and file types. Symlinks are not followed. Throws a `NixIoError` if
+   * the directory can't be read.
+   */
+  var readDir: (path: string) => [string, FileType][];
+
+  /**
+   * Whether the given path exists. Symlinks are followed.
+   */
+  var pathExists: (path: string) => boolean;
+
+  /**
+   * The file type of the given path. Symlinks are not followed. Throws a
+   * `NixIoError` if the path doesn't exist.
+   */
+  var readFileType: (path: string) => FileType;
+
+  type FileType = "regular" | "directory" | "symlink" | "unknown";
+
+  /**
+   * Hash the UTF-8 bytes of the string with the given algorithm (`md5`,
+   * `sha1`, `sha256` or `sha512`) and return the hash in the given format
+   * (`base16`, `nix32`, `base64` or `sri`). Throws a `NixOtherError` if the
+   * algorithm or the format is unknown.
+   */
+  var hashString: (algo: string, data: string, format: string) => string;
+
+  /**
+   * Like `hashString`, but hashes the contents of the file at the given path.
+   */
+  var hashFile: (algo: string, path: string, format: string) => string;
+
+  /**
+   * Convert the hash into the given format. The algorithm may be omitted if
+   * the hash is in the SRI format or prefixed with `<algo>:`.
+   */
+  var convertHash: (
+    hash: string,
+    algo: string | undefined,
+    format: string,
+  ) => string;
+
+  /**
+   * Return the value of the environment variable, or an empty string if it
+   * isn't set or the environment is hidden in pure evaluation mode.
+   */
+  var getEnv: (name: string) => string;
+
+  /**
+   * Values that the host adds to the builtins set. The host sets them before
+   * every evaluation. `currentTime` is missing in pure evaluation mode.
+   */
+  var builtinConstants:
+    | {
+        currentSystem: string;
+        currentTime?: number;
+        nixVersion: string;
+        langVersion: number;
+      }
+    | undefined;
 
   /**
    * Log the string provided, purely for debugging purposes.
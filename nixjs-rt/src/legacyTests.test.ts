@@ -17,7 +17,7 @@ import {
   Path,
   StrictAttrset,
 } from "./lib";
-import { evalCtx, keyVals, toAttrpath } from "./testUtils";
+import { evalCtx, getBuiltin, keyVals, toAttrpath } from "./testUtils";
 
 // Apply:
 test("calling a lambda should return its value", () => {
@@ -124,6 +124,19 @@ test("'/' operator on mixed integers and floats", () => {
   );
 });
 
+// Assert:
+test("'assert' expression evaluates the body when the condition holds", () => {
+  expect(
+    n.assertExpr(evalCtx(), n.TRUE, "true", (_) => new NixInt(1n)).toJs(),
+  ).toBe(1n);
+});
+
+test("'assert' expression throws when the condition fails", () => {
+  expect(() =>
+    n.assertExpr(evalCtx(), n.FALSE, "false", (_) => new NixInt(1n)),
+  ).toThrow(n.NixError);
+});
+
 // Attrset:
 test("attrset construction", () => {
   expect(attrset(evalCtx(), keyVals()).toJs()).toStrictEqual(new Map());
@@ -535,6 +548,32 @@ test("pattern lambda with arguments binding", () => {
   ).toBe(1);
 });
 
+test("builtins are primops", () => {
+  const builtinAdd = getBuiltin("add");
+  expect(builtinAdd).toBeInstanceOf(n.PrimOp);
+  expect((builtinAdd as n.PrimOp).name).toBe("add");
+  expect(builtinAdd.typeOf()).toBe("lambda");
+});
+
+test("'builtins.tryEval' catches import failures", () => {
+  const tryEval = getBuiltin("tryEval");
+  const failedImport = new Lazy(evalCtx(), (_) => {
+    throw n.importFailedError("/foo.nix", "No such file or directory");
+  });
+  expect(tryEval.apply(failedImport).toJs()).toStrictEqual(
+    new Map([
+      ["success", false],
+      ["value", false],
+    ]),
+  );
+  expect(tryEval.apply(new NixInt(1n)).toJs()).toStrictEqual(
+    new Map<string, any>([
+      ["success", true],
+      ["value", 1n],
+    ]),
+  );
+});
+
 // Lazy:
 test("'Lazy.toStrict' evaluates the body only once", () => {
   let sentinel = new NixFloat(0);
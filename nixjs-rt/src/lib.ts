@@ -20,6 +20,7 @@ import {
   couldntFindVariableError,
 } from "./errors/variable";
 import { NixAbortError } from "./errors/abort";
+import { assertionFailedError } from "./errors/assertion";
 import { isAbsolutePath, joinPaths, normalizePath } from "./utils";
 
 // Error re-exports
@@ -29,10 +30,18 @@ export {
   NixAttributeAlreadyDefinedError,
   NixMissingAttributeError,
 } from "./errors/attribute";
-export { NixOtherError } from "./errors/other";
+export { NixOtherError, otherError } from "./errors/other";
 export { NixTypeMismatchError } from "./errors/typeError";
 export { NixCouldntFindVariableError } from "./errors/variable";
 export { NixAbortError } from "./errors/abort";
+export { NixAssertionFailedError } from "./errors/assertion";
+export { NixImportFailedError, importFailedError } from "./errors/import";
+export { NixIoError, ioError } from "./errors/io";
+export {
+  NixAccessForbiddenError,
+  accessForbiddenError,
+} from "./errors/restricted";
+export { NixParseError, parseError } from "./errors/parse";
 
 // Types:
 export class EvalException extends Error {
@@ -938,19 +947,31 @@ export class NixNull extends NixType {
 
 export class NixString extends NixType {
   readonly value: string;
+  /**
+   * The paths that were coerced into this string, which Nix calls the
+   * context of the string.
+   */
+  readonly context: ReadonlySet<string>;
 
-  constructor(value: string) {
+  constructor(value: string, context: ReadonlySet<string> = new Set()) {
     super();
     this.value = value;
+    this.context = context;
   }
 
   override add(rhs: NixType): NixType {
     rhs = rhs.toStrict();
     if (rhs instanceof NixString) {
-      return new NixString(this.value + rhs.value);
+      return new NixString(
+        this.value + rhs.value,
+        new Set([...this.context, ...rhs.context]),
+      );
     }
     if (rhs instanceof Path) {
-      return new NixString(normalizePath(this.value + rhs.path));
+      return new NixString(
+        normalizePath(this.value + rhs.path),
+        new Set([...this.context, rhs.path]),
+      );
     }
     return super.add(rhs);
   }
@@ -1200,6 +1221,23 @@ export class Lambda extends NixType {
   }
 }
 
+/**
+ * A lambda whose argument is an attrset pattern, e.g. `{ a, b ? 1 }: a + b`.
+ */
+export class PatternLambda extends Lambda {}
+
+/**
+ * A lambda that is built into the runtime, e.g. `builtins.add`.
+ */
+export class PrimOp extends Lambda {
+  readonly name: string;
+
+  constructor(name: string, body: (param: NixType) => NixType) {
+    super(body);
+    this.name = name;
+  }
+}
+
 export const NULL = new NixNull();
 export const TRUE = new NixBool(true);
 export const FALSE = new NixBool(false);
@@ -1209,6 +1247,19 @@ export function nixBoolFromJs(value: boolean): NixBool {
   return value ? TRUE : FALSE;
 }
 
+// Assert:
+export function assertExpr(
+  evalCtx: EvalCtx,
+  condition: NixType,
+  conditionText: string,
+  body: Body,
+): NixType {
+  if (!condition.asBoolean()) {
+    throw assertionFailedError(conditionText);
+  }
+  return body(evalCtx);
+}
+
 // Attrset:
 export function attrset(evalCtx: EvalCtx, entries: AttrsetBody): Attrset {
   return new LazyAttrset(evalCtx, false, entries);
@@ -1225,7 +1276,18 @@ function _createBuiltinsAttrset() {
   const builtins = new Map();
 
   for (const [name, value] of Object.entries(builtinsRecord)) {
-    builtins.set(name, new Lambda(value));
+    builtins.set(name, new PrimOp(name, value));
+  }
+
+  // The constants are missing if there is no host, e.g. in tests.
+  const constants = globalThis.builtinConstants;
+  if (constants !== undefined) {
+    builtins.set("currentSystem", new NixString(constants.currentSystem));
+    builtins.set("nixVersion", new NixString(constants.nixVersion));
+    builtins.set("langVersion", new NixInt(BigInt(constants.langVersion)));
+    if (constants.currentTime !== undefined) {
+      builtins.set("currentTime", new NixInt(BigInt(constants.currentTime)));
+    }
   }
 
   return new StrictAttrset(builtins);
@@ -1250,10 +1312,18 @@ export function patternLambda(
   patterns: [[string, any]],
   body: Body,
 ): any {
-  return new Lambda((param: Attrset) => {
+  return new PatternLambda((param) => {
+    const paramStrict = param.toStrict();
+    if (!(paramStrict instanceof Attrset)) {
+      throw typeMismatchError(
+        paramStrict,
+        Attrset,
+        err`Expected ${errType(Attrset)} as the function argument, but got ${errType(paramStrict)}`,
+      );
+    }
     let paramScope = new Map();
     for (const [paramName, defaultValue] of patterns) {
-      let paramValue = param.lookup(paramName);
+      let paramValue = paramStrict.lookup(paramName);
       if (paramValue === undefined) {
         if (defaultValue === undefined) {
           throw functionCallWithoutArgumentError(paramName);
@@ -1274,6 +1344,34 @@ export function letIn(evalCtx: EvalCtx, attrs: Scope, body: Body): NixType {
   return body(evalCtx.withShadowingScope(attrs));
 }
 
+// String:
+/**
+ * Concatenates the literal parts and the interpolated values of a string like
+ * `"a${b}"`. The contexts of interpolated strings and the interpolated paths
+ * make up the context of the result.
+ */
+export function interpolate(parts: (string | NixType)[]): NixString {
+  let value = "";
+  const context = new Set<string>();
+  for (const part of parts) {
+    if (typeof part === "string") {
+      value += part;
+      continue;
+    }
+    const partStrict = part.toStrict();
+    if (partStrict instanceof NixString) {
+      value += partStrict.value;
+      partStrict.context.forEach((path) => context.add(path));
+    } else if (partStrict instanceof Path) {
+      value += partStrict.path;
+      context.add(partStrict.path);
+    } else {
+      value += partStrict.asString();
+    }
+  }
+  return new NixString(value, context);
+}
+
 // Path:
 export function toPath(evalCtx: EvalCtx, path: string): Path {
   if (!isAbsolutePath(path)) {
@@ -1283,6 +1381,19 @@ export function toPath(evalCtx: EvalCtx, path: string): Path {
 }
 
 // Utilities:
+/**
+ * Calls a lambda with an attrset pattern with an empty attrset, like
+ * `nix eval` does with the value it evaluates, so that the defaults of the
+ * pattern are used. Other values are returned unchanged.
+ */
+export function autoCallFunction(value: NixType): NixType {
+  const valueStrict = value.toStrict();
+  if (valueStrict instanceof PatternLambda) {
+    return valueStrict.apply(new StrictAttrset(new Map()));
+  }
+  return value;
+}
+
 export function recursiveStrict(value: NixType): NixType {
   if (value instanceof Attrset) {
     return recursiveStrictAttrset(value);
@@ -1346,6 +1457,7 @@ function _buildGlobalScope() {
 
   // Nix makes some builtins available directly in the global scope:
   scope.set("abort", builtins.lookup("abort"));
+  scope.set("import", builtins.lookup("import"));
 
   return new GlobalScope(scope);
 }
@@ -1,15 +1,21 @@
-use std::collections::HashMap;
+use std::path::Path;
 
-use crate::cmd::{to_cmd_err, RixSubCommand};
-use crate::eval::error::NixError;
-use crate::eval::execution;
-use crate::eval::types::Value;
+use crate::cmd::{
+    print_and_err, print_and_err_as_json, print_and_err_in_source, print_and_err_with_trace,
+    ErrorCategory, RixSubCommand,
+};
+use crate::eval::error::{NixError, NixErrorKind, NixErrorMessagePart};
+use crate::eval::eval_cache::EvalCache;
+use crate::eval::execution::Evaluator;
+use crate::eval::modules::ROOT_MODULE_NAME;
+use crate::eval::restrictions::{EvalMode, EvalRestrictions};
+use crate::eval::types::{NixTypeKind, Value};
 use clap::{Arg, ArgAction, ArgMatches};
 
 pub fn cmd() -> RixSubCommand {
     RixSubCommand {
         name: "eval",
-        handler: |args| to_cmd_err(handle_cmd(args)),
+        handler: handle_cmd,
         cmd: |subcommand| {
             subcommand
                 .about("evaluates the given expression and prints the result")
@@ -18,52 +24,281 @@ pub fn cmd() -> RixSubCommand {
                     Arg::new("expr")
                         .long("expr")
                         .action(ArgAction::Set)
+                        .conflicts_with("file")
                         .help("The expression to evaluate. Installables are treated as attribute paths of the attrset returned by the expression."),
                 )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .short('f')
+                        .action(ArgAction::Set)
+                        .help("The file to evaluate. Installables are treated as attribute paths of the attrset returned by the file."),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("raw")
+                        .help("Print the result as JSON."),
+                )
+                .arg(
+                    Arg::new("raw")
+                        .long("raw")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the resulting string without quotes or escaping."),
+                )
+                .arg(
+                    Arg::new("pretty")
+                        .long("pretty")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["json", "raw"])
+                        .help("Print nested lists and attrsets over multiple indented lines."),
+                )
+                .arg(
+                    Arg::new("show-trace")
+                        .long("show-trace")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the Nix expressions that were being evaluated when an error occurred."),
+                )
+                .arg(
+                    Arg::new("error-format")
+                        .long("error-format")
+                        .value_parser(["human", "json"])
+                        .default_value("human")
+                        .help("How to print errors. With 'json', every error is printed to stderr as a single line of JSON."),
+                )
+                .arg(
+                    Arg::new("restrict-eval")
+                        .long("restrict-eval")
+                        .action(ArgAction::SetTrue)
+                        .help("Only allow access to files under the paths given with '-I', and hide environment variables."),
+                )
+                .arg(
+                    Arg::new("pure-eval")
+                        .long("pure-eval")
+                        .action(ArgAction::SetTrue)
+                        .help("Like '--restrict-eval', but also hide the current time."),
+                )
+                .arg(
+                    Arg::new("system")
+                        .long("system")
+                        .action(ArgAction::Set)
+                        .help("The system type to evaluate for, e.g. 'aarch64-linux'. Defaults to the system that rix runs on."),
+                )
+                .arg(
+                    Arg::new("include")
+                        .short('I')
+                        .action(ArgAction::Append)
+                        .value_name("PATH")
+                        .help("Allow access to files under the given path in restricted and pure mode. Also accepts 'name=path'."),
+                )
+                .arg(
+                    Arg::new("no-eval-cache")
+                        .long("no-eval-cache")
+                        .action(ArgAction::SetTrue)
+                        .help("Don't read or write the cache of transpiled Nix files in '$XDG_CACHE_HOME/rix'. The cache is never evicted, so delete the directory to reclaim space."),
+                )
         },
     }
 }
 
-pub fn handle_cmd(parsed_args: &ArgMatches) -> Result<(), NixError> {
-    let expr = parsed_args
-        .get_one::<String>("expr")
-        .ok_or("You must use the '--expr' option. Nothing else is implemented :)")?;
+pub fn handle_cmd(parsed_args: &ArgMatches) -> Result<(), ErrorCategory> {
+    let json_errors = parsed_args
+        .get_one::<String>("error-format")
+        .map(String::as_str)
+        == Some("json");
+    let expr_arg = parsed_args.get_one::<String>("expr");
+    let file_arg = parsed_args
+        .get_one::<String>("file")
+        .filter(|_| expr_arg.is_none());
+    // Errors outside of the evaluation are attributed to the evaluated file.
+    let report_source_name = file_arg.map_or(ROOT_MODULE_NAME, String::as_str);
+    let report = |err: NixError| {
+        if json_errors {
+            print_and_err_as_json(err, report_source_name)
+        } else {
+            print_and_err(err)
+        }
+    };
+
+    let installable = parsed_args
+        .get_one::<String>("INSTALLABLE")
+        .map_or("", String::as_str);
+    let attr_path = parse_attr_path(installable).map_err(report)?;
 
-    let current_dir = std::env::current_dir().map_err(|_| "Couldn't get the current directory")?;
+    let current_dir =
+        std::env::current_dir().map_err(|_| report("Couldn't get the current directory".into()))?;
+    let (expr, source_name, workdir) = if let Some(expr) = expr_arg {
+        (
+            expr.clone(),
+            ROOT_MODULE_NAME.to_owned(),
+            current_dir.clone(),
+        )
+    } else if let Some(file) = file_arg {
+        let file_path = Path::new(file).canonicalize().map_err(|err| {
+            report(NixError::io(
+                file,
+                format!("Failed to resolve the path '{file}'. Error: {err}."),
+            ))
+        })?;
+        let expr = std::fs::read_to_string(&file_path).map_err(|err| {
+            report(NixError::io(
+                file,
+                format!("Failed to read the file '{file}'. Error: {err}."),
+            ))
+        })?;
+        let workdir = file_path.parent().unwrap_or(&file_path).to_path_buf();
+        (expr, file_path.to_string_lossy().into_owned(), workdir)
+    } else {
+        return Err(report(NixError::usage(
+            "You must use either the '--expr' or the '--file' option.".to_owned(),
+        )));
+    };
 
-    print_value(&execution::evaluate(expr, &current_dir)?);
-    println!();
+    let mut evaluator = Evaluator::new().map_err(report)?;
+    if !parsed_args.get_flag("no-eval-cache") {
+        evaluator.set_eval_cache(EvalCache::in_user_cache_dir());
+    }
+    evaluator.set_restrictions(eval_restrictions(parsed_args, &current_dir));
+    if let Some(system) = parsed_args.get_one::<String>("system") {
+        evaluator.set_system(system);
+    }
+    let value = evaluator
+        .evaluate_attr_path(&expr, &workdir, &attr_path)
+        .map_err(|err| {
+            if json_errors {
+                print_and_err_as_json(err, &source_name)
+            } else if parsed_args.get_flag("show-trace") {
+                print_and_err_with_trace(err, &source_name, &expr, &evaluator)
+            } else {
+                print_and_err_in_source(err, &source_name, &expr, &evaluator)
+            }
+        })?;
+
+    if parsed_args.get_flag("json") {
+        let json = value_to_json(&value).map_err(report)?;
+        println!("{json}");
+    } else if parsed_args.get_flag("raw") {
+        print!("{}", value_to_raw_string(&value).map_err(report)?);
+    } else if parsed_args.get_flag("pretty") {
+        println!("{value:#}");
+    } else {
+        println!("{value}");
+    }
     Ok(())
 }
 
-fn print_value(value: &Value) {
-    match value {
-        Value::AttrSet(hash_map) => print_attrset(hash_map),
-        Value::Bool(boolean) => print!("{boolean}"),
-        Value::Float(float) => print!("{float}"),
-        Value::Int(int) => print!("{int}"),
-        Value::Lambda => print!("<LAMBDA>"),
-        Value::List(vector) => print_list(vector),
-        Value::Path(string) => print!("\"{string}\""),
-        Value::Str(string) => print!("\"{string}\""),
+fn eval_restrictions(parsed_args: &ArgMatches, current_dir: &Path) -> EvalRestrictions {
+    let mode = if parsed_args.get_flag("pure-eval") {
+        EvalMode::Pure
+    } else if parsed_args.get_flag("restrict-eval") {
+        EvalMode::Restricted
+    } else {
+        EvalMode::Impure
+    };
+    let allowed_paths = parsed_args
+        .get_many::<String>("include")
+        .unwrap_or_default()
+        .map(|include| {
+            // Like in `nix`, an entry can also be of the form `name=path`.
+            let path = include
+                .split_once('=')
+                .map_or(include.as_str(), |(_, path)| path);
+            current_dir.join(path)
+        })
+        .collect();
+    EvalRestrictions {
+        mode,
+        allowed_paths,
     }
 }
 
-fn print_list(vector: &Vec<Value>) {
-    print!("[ ");
-    for value in vector {
-        print_value(value);
-        print!(" ");
+/// Splits an attribute path like `a."b.c".d` into its attribute names.
+fn parse_attr_path(attr_path: &str) -> Result<Vec<String>, NixError> {
+    let mut attr_names = Vec::new();
+    if attr_path.is_empty() {
+        return Ok(attr_names);
+    }
+    let mut attr_name = String::new();
+    let mut chars = attr_path.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '.' => attr_names.push(std::mem::take(&mut attr_name)),
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(char) => attr_name.push(char),
+                    None => {
+                        return Err(NixError::usage(format!(
+                            "missing closing quote in selection path '{attr_path}'"
+                        )))
+                    }
+                }
+            },
+            char => attr_name.push(char),
+        }
     }
-    print!("]");
+    attr_names.push(attr_name);
+    if attr_names.iter().any(String::is_empty) {
+        return Err(NixError::usage(format!(
+            "empty attribute name in selection path '{attr_path}'"
+        )));
+    }
+    Ok(attr_names)
+}
+
+/// Converts the value into JSON the same way `nix eval --json` does.
+fn value_to_json(value: &Value) -> Result<serde_json::Value, NixError> {
+    Ok(match value {
+        Value::AttrSet(attrs) => serde_json::Value::Object(
+            attrs
+                .iter()
+                .map(|(attr_name, value)| Ok((attr_name.clone(), value_to_json(value)?)))
+                .collect::<Result<_, NixError>>()?,
+        ),
+        Value::Bool(boolean) => (*boolean).into(),
+        Value::Float(float) => (*float).into(),
+        Value::Int(int) => (*int).into(),
+        Value::Lambda | Value::PrimOp(_) => {
+            return Err(NixError {
+                message: vec![NixErrorMessagePart::Plain(
+                    "cannot convert a function to JSON".to_owned(),
+                )],
+                kind: NixErrorKind::Other {
+                    codename: "json-function".to_owned(),
+                },
+                trace: Vec::new(),
+            })
+        }
+        Value::List(vector) => serde_json::Value::Array(
+            vector
+                .iter()
+                .map(value_to_json)
+                .collect::<Result<_, NixError>>()?,
+        ),
+        Value::Null => serde_json::Value::Null,
+        Value::Path(path) => path.clone().into(),
+        Value::Str(string) => string.value.clone().into(),
+    })
 }
 
-fn print_attrset(hash_map: &HashMap<String, Value>) {
-    print!("{{ ");
-    for (attr_name, value) in hash_map {
-        print!("{attr_name} = ");
-        print_value(value);
-        print!("; ");
+fn value_to_raw_string(value: &Value) -> Result<&str, NixError> {
+    match value {
+        Value::Path(path) => Ok(path),
+        Value::Str(string) => Ok(&string.value),
+        _ => Err(NixError {
+            message: vec![
+                NixErrorMessagePart::Plain("Cannot print ".to_owned()),
+                NixErrorMessagePart::Highlighted(value.type_kind().to_string()),
+                NixErrorMessagePart::Plain(
+                    " as a raw string, expected a string or a path.".to_owned(),
+                ),
+            ],
+            kind: NixErrorKind::TypeMismatch {
+                expected: vec![NixTypeKind::String, NixTypeKind::Path],
+                got: value.type_kind(),
+            },
+            trace: Vec::new(),
+        }),
     }
-    print!("}}");
 }
@@ -1,26 +1,400 @@
 pub mod eval;
 pub mod transpile;
-use clap::{ArgMatches, Command};
+use clap::{Arg, ArgMatches, Command};
 use colored::*;
+use serde_json::json;
+use std::io::IsTerminal;
 use std::process::ExitCode;
 
-use crate::eval::error::NixError;
+use crate::eval::error::{NixError, NixErrorKind, NixErrorMessagePart, NixStackFrame};
+use crate::eval::execution::Evaluator;
+use crate::eval::modules::ROOT_MODULE_NAME;
+use crate::eval::types::NixTypeKind;
 
 pub struct RixSubCommand {
     pub name: &'static str,
     pub cmd: fn(Command) -> Command,
-    pub handler: fn(&ArgMatches) -> Result<(), ExitCode>,
+    pub handler: fn(&ArgMatches) -> Result<(), ErrorCategory>,
+}
+
+/// The category of an error, which determines the exit code of `rix`:
+///
+/// | Exit code | Category                                                    |
+/// |-----------|-------------------------------------------------------------|
+/// | 1         | An error in the evaluated Nix code, e.g. a failed assertion |
+/// | 2         | Invalid command line arguments                              |
+/// | 3         | A syntax error in the Nix code                              |
+/// | 4         | A file that couldn't be read, imported or accessed          |
+/// | 5         | An internal error of `rix`                                  |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    Eval = 1,
+    Usage = 2,
+    Parse = 3,
+    Io = 4,
+    Internal = 5,
+}
+
+impl ErrorCategory {
+    pub fn of(kind: &NixErrorKind) -> Self {
+        match kind {
+            NixErrorKind::Abort { .. }
+            | NixErrorKind::AssertionFailed { .. }
+            | NixErrorKind::CouldntFindVariable { .. }
+            | NixErrorKind::TypeMismatch { .. }
+            | NixErrorKind::Other { .. }
+            | NixErrorKind::MissingAttribute { .. }
+            | NixErrorKind::AttributeAlreadyDefined { .. }
+            | NixErrorKind::FunctionCallWithoutArgument { .. } => ErrorCategory::Eval,
+            NixErrorKind::Usage { .. } => ErrorCategory::Usage,
+            NixErrorKind::ParseError { .. } => ErrorCategory::Parse,
+            NixErrorKind::ImportFailed { .. }
+            | NixErrorKind::Io { .. }
+            | NixErrorKind::AccessForbidden { .. } => ErrorCategory::Io,
+            NixErrorKind::UnexpectedJsError { .. } | NixErrorKind::UnexpectedRustError { .. } => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    pub fn exit_code(self) -> ExitCode {
+        ExitCode::from(self as u8)
+    }
+}
+
+/// The `--color` option, which all subcommands accept.
+pub fn color_arg() -> Arg {
+    Arg::new("color")
+        .long("color")
+        .global(true)
+        .value_parser(["auto", "always", "never"])
+        .default_value("auto")
+        .help("Whether to use colors in error messages. With 'auto', colors are used if stderr is a terminal and 'NO_COLOR' is not set.")
+}
+
+/// Enables or disables colored output as requested by the `--color` option.
+pub fn set_color_mode(parsed_args: &ArgMatches) {
+    let use_color = match parsed_args.get_one::<String>("color").map(String::as_str) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            !no_color && std::io::stderr().is_terminal()
+        }
+    };
+    colored::control::set_override(use_color);
 }
 
 pub fn print_err(msg: NixError) {
-    eprintln!("{}: {}", "error".red(), msg);
+    eprintln!("{}: {}", "error".red(), colored_message(&msg));
+}
+
+/// Renders the error message with its highlighted parts in bold magenta, like
+/// `nix` does.
+fn colored_message(msg: &NixError) -> String {
+    msg.message
+        .iter()
+        .map(|part| match part {
+            NixErrorMessagePart::Plain(text) => text.clone(),
+            NixErrorMessagePart::Highlighted(text) => text.magenta().bold().to_string(),
+        })
+        .collect()
+}
+
+/// Prints the error and, if the error points at a location in Nix code, an
+/// excerpt of the code with the location underlined. Locations in the
+/// evaluated expression are shown in the given source, and other files are
+/// read through the evaluator.
+pub fn print_err_in_source(msg: NixError, source_name: &str, source: &str, evaluator: &Evaluator) {
+    let excerpt = parse_error_excerpt(&msg.kind, source_name, source, evaluator);
+    print_err(msg);
+    if let Some(excerpt) = excerpt {
+        eprint!("{excerpt}");
+    }
+}
+
+/// Prints the error preceded by its Nix stack trace, like `nix --show-trace`.
+/// Frames in the evaluated expression are shown in the given source, and other
+/// files are read through the evaluator.
+pub fn print_err_with_trace(msg: NixError, source_name: &str, source: &str, evaluator: &Evaluator) {
+    if msg.trace.is_empty() {
+        return print_err_in_source(msg, source_name, source, evaluator);
+    }
+    eprintln!("{}:", "error".red());
+    for frame in &msg.trace {
+        eprint!(
+            "{}",
+            trace_frame_excerpt(frame, source_name, source, evaluator)
+        );
+    }
+    let excerpt = parse_error_excerpt(&msg.kind, source_name, source, evaluator);
+    eprintln!("       {}: {}", "error".red(), colored_message(&msg));
+    if let Some(excerpt) = excerpt {
+        eprint!("{excerpt}");
+    }
+}
+
+/// Prints the error as a single line of JSON, for tools that wrap `rix`. Frames
+/// in the evaluated expression are attributed to the given source name.
+pub fn print_err_as_json(msg: NixError, source_name: &str) {
+    eprintln!("{}", error_to_json(&msg, source_name));
+}
+
+fn error_to_json(msg: &NixError, source_name: &str) -> serde_json::Value {
+    let file_name = |file: &str| {
+        if file == ROOT_MODULE_NAME {
+            source_name.to_owned()
+        } else {
+            file.to_owned()
+        }
+    };
+    let (kind, fields) = error_kind_to_json(&msg.kind, file_name);
+    let message: Vec<_> = msg
+        .message
+        .iter()
+        .map(|part| match part {
+            NixErrorMessagePart::Plain(text) => json!({ "type": "plain", "text": text }),
+            NixErrorMessagePart::Highlighted(text) => {
+                json!({ "type": "highlighted", "text": text })
+            }
+        })
+        .collect();
+    let trace: Vec<_> = msg
+        .trace
+        .iter()
+        .map(|frame| {
+            json!({
+                "file": file_name(&frame.file),
+                "line": frame.line,
+                "column": frame.column,
+                "description": frame.description,
+            })
+        })
+        .collect();
+    let location = match (&msg.kind, msg.trace.last()) {
+        (
+            NixErrorKind::ParseError {
+                file, line, column, ..
+            },
+            _,
+        ) => {
+            json!({ "file": file_name(file), "line": line, "column": column })
+        }
+        (_, Some(frame)) => {
+            json!({ "file": file_name(&frame.file), "line": frame.line, "column": frame.column })
+        }
+        (_, None) => serde_json::Value::Null,
+    };
+    json!({
+        "kind": kind,
+        "fields": fields,
+        "message": message,
+        "location": location,
+        "trace": trace,
+    })
+}
+
+/// Returns the name of the error kind and its fields. File names are shown
+/// as returned by `file_name`.
+fn error_kind_to_json(
+    kind: &NixErrorKind,
+    file_name: impl Fn(&str) -> String,
+) -> (&'static str, serde_json::Value) {
+    match kind {
+        NixErrorKind::Abort { message } => ("Abort", json!({ "message": message })),
+        NixErrorKind::AssertionFailed { condition } => {
+            ("AssertionFailed", json!({ "condition": condition }))
+        }
+        NixErrorKind::CouldntFindVariable { var_name } => {
+            ("CouldntFindVariable", json!({ "var_name": var_name }))
+        }
+        NixErrorKind::TypeMismatch { expected, got } => (
+            "TypeMismatch",
+            json!({
+                "expected": expected.iter().map(type_kind_name).collect::<Vec<_>>(),
+                "got": type_kind_name(got),
+            }),
+        ),
+        NixErrorKind::Other { codename } => ("Other", json!({ "codename": codename })),
+        NixErrorKind::MissingAttribute { attr_path } => {
+            ("MissingAttribute", json!({ "attr_path": attr_path }))
+        }
+        NixErrorKind::AttributeAlreadyDefined { attr_path } => {
+            ("AttributeAlreadyDefined", json!({ "attr_path": attr_path }))
+        }
+        NixErrorKind::FunctionCallWithoutArgument { argument } => (
+            "FunctionCallWithoutArgument",
+            json!({ "argument": argument }),
+        ),
+        NixErrorKind::ImportFailed { path, reason } => {
+            ("ImportFailed", json!({ "path": path, "reason": reason }))
+        }
+        NixErrorKind::ParseError {
+            file,
+            line,
+            column,
+            message,
+        } => (
+            "ParseError",
+            json!({
+                "file": file_name(file),
+                "line": line,
+                "column": column,
+                "message": message,
+            }),
+        ),
+        NixErrorKind::Io { path, message } => ("Io", json!({ "path": path, "message": message })),
+        NixErrorKind::AccessForbidden { path } => ("AccessForbidden", json!({ "path": path })),
+        NixErrorKind::Usage { message } => ("Usage", json!({ "message": message })),
+        NixErrorKind::UnexpectedJsError { message } => {
+            ("UnexpectedJsError", json!({ "message": message }))
+        }
+        NixErrorKind::UnexpectedRustError { message } => {
+            ("UnexpectedRustError", json!({ "message": message }))
+        }
+    }
+}
+
+/// The name of the type as returned by `builtins.typeOf`.
+fn type_kind_name(kind: &NixTypeKind) -> &'static str {
+    match kind {
+        NixTypeKind::Bool => "bool",
+        NixTypeKind::Float => "float",
+        NixTypeKind::Int => "int",
+        NixTypeKind::List => "list",
+        NixTypeKind::Null => "null",
+        NixTypeKind::String => "string",
+        NixTypeKind::Path => "path",
+        NixTypeKind::Lambda => "lambda",
+        NixTypeKind::Set => "set",
+    }
+}
+
+/// Returns the name under which the Nix file is shown and its source, if it
+/// can be read. The evaluated expression is shown as the given source.
+fn file_source<'a>(
+    file: &'a str,
+    source_name: &'a str,
+    source: &str,
+    evaluator: &Evaluator,
+) -> (&'a str, Option<String>) {
+    if file == ROOT_MODULE_NAME {
+        (source_name, Some(source.to_owned()))
+    } else {
+        (file, evaluator.nix_source(file))
+    }
+}
+
+fn trace_frame_excerpt(
+    frame: &NixStackFrame,
+    source_name: &str,
+    source: &str,
+    evaluator: &Evaluator,
+) -> String {
+    let (file, file_source) = file_source(&frame.file, source_name, source, evaluator);
+    let mut excerpt = format!(
+        "       … {}\n         at {file}:{}:{}:\n\n",
+        frame.description, frame.line, frame.column
+    );
+    if let Some(file_source) = file_source {
+        let column = utf16_column_to_char_column(&file_source, frame.line, frame.column);
+        excerpt += &source_line_excerpt(&file_source, frame.line, column);
+        excerpt += "\n";
+    }
+    excerpt
+}
+
+/// Returns the location of a parse error with an excerpt of the file, if it
+/// can be read.
+fn parse_error_excerpt(
+    kind: &NixErrorKind,
+    source_name: &str,
+    source: &str,
+    evaluator: &Evaluator,
+) -> Option<String> {
+    let NixErrorKind::ParseError {
+        file, line, column, ..
+    } = kind
+    else {
+        return None;
+    };
+    let (file, file_source) = file_source(file, source_name, source, evaluator);
+    let mut excerpt = format!("\n       at {file}:{line}:{column}:\n\n");
+    if let Some(file_source) = file_source {
+        excerpt += &source_line_excerpt(&file_source, *line, *column);
+        excerpt += "\n";
+    }
+    Some(excerpt)
+}
+
+/// Shows the given line of the source and underlines the given column, which
+/// counts characters.
+fn source_line_excerpt(source: &str, line: usize, column: usize) -> String {
+    let line_src = source_line(source, line);
+    let line_number = line.to_string();
+    let gutter = " ".repeat(line_number.len());
+    let indent = " ".repeat(column.saturating_sub(1));
+    format!(
+        "       {line_number}| {line_src}\n       {gutter}| {indent}{}\n",
+        "^".red()
+    )
 }
 
-pub fn to_cmd_err(result: Result<(), NixError>) -> Result<(), ExitCode> {
+/// Converts a one-based column that counts UTF-16 code units, like the columns
+/// of stack frames, into one that counts characters.
+fn utf16_column_to_char_column(source: &str, line: usize, column: usize) -> usize {
+    let mut utf16_len = 0;
+    let chars = source_line(source, line)
+        .chars()
+        .take_while(|char| {
+            utf16_len += char.len_utf16();
+            utf16_len < column
+        })
+        .count();
+    chars + 1
+}
+
+fn source_line(source: &str, line: usize) -> &str {
+    source
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default()
+}
+
+pub fn to_cmd_err(result: Result<(), NixError>) -> Result<(), ErrorCategory> {
     result.map_err(print_and_err)
 }
 
-pub fn print_and_err(msg: NixError) -> ExitCode {
+pub fn print_and_err(msg: NixError) -> ErrorCategory {
+    let category = ErrorCategory::of(&msg.kind);
     print_err(msg);
-    ExitCode::FAILURE
+    category
+}
+
+pub fn print_and_err_in_source(
+    msg: NixError,
+    source_name: &str,
+    source: &str,
+    evaluator: &Evaluator,
+) -> ErrorCategory {
+    let category = ErrorCategory::of(&msg.kind);
+    print_err_in_source(msg, source_name, source, evaluator);
+    category
+}
+
+pub fn print_and_err_as_json(msg: NixError, source_name: &str) -> ErrorCategory {
+    let category = ErrorCategory::of(&msg.kind);
+    print_err_as_json(msg, source_name);
+    category
+}
+
+pub fn print_and_err_with_trace(
+    msg: NixError,
+    source_name: &str,
+    source: &str,
+    evaluator: &Evaluator,
+) -> ErrorCategory {
+    let category = ErrorCategory::of(&msg.kind);
+    print_err_with_trace(msg, source_name, source, evaluator);
+    category
 }
@@ -1,6 +1,9 @@
+use std::path::Path;
+
 use crate::cmd::{to_cmd_err, RixSubCommand};
 use crate::eval::emit_js;
 use crate::eval::error::NixError;
+use crate::eval::modules::ROOT_MODULE_NAME;
 use clap::{Arg, ArgAction, ArgMatches};
 
 pub fn cmd() -> RixSubCommand {
@@ -10,28 +13,82 @@ pub fn cmd() -> RixSubCommand {
         cmd: |subcommand| {
             subcommand
                 .about("transpiles the given nix expression file into JavaScript.")
-                .arg(Arg::new("EXPRESSION").help("The nix expression to transpile."))
+                .arg(Arg::new("EXPRESSION").help("The nix expression file to transpile."))
                 .arg(
                     Arg::new("expr")
                         .long("expr")
                         .action(ArgAction::SetTrue)
                         .help("The 'EXPRESSION' argument will be treated as an expression rather than a file."),
                 )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .action(ArgAction::Set)
+                        .help("Write the JavaScript into the given file rather than to the standard output."),
+                )
+                .arg(
+                    Arg::new("source-map")
+                        .long("source-map")
+                        .action(ArgAction::SetTrue)
+                        .requires("output")
+                        .help("Also write a source map of the JavaScript to '<output>.map'."),
+                )
         },
     }
 }
 
 pub fn handle_cmd(parsed_args: &ArgMatches) -> Result<(), NixError> {
-    let expression = parsed_args
-        .get_one::<String>("EXPRESSION")
-        .ok_or("You must provide a single expression to transpile.")?;
+    let expression = parsed_args.get_one::<String>("EXPRESSION").ok_or_else(|| {
+        NixError::usage("You must provide a single expression to transpile.".to_owned())
+    })?;
     let is_expression = parsed_args.get_one::<bool>("expr").unwrap();
-    if *is_expression {
-        let js_source = emit_js::emit_module(expression)
-            .map_err(|_err| "Failed to transpile the expression.".to_owned())?;
-        print!("{js_source}");
+    let (nix_source, source_name) = if *is_expression {
+        (expression.clone(), ROOT_MODULE_NAME.to_owned())
     } else {
-        todo!("Support to transpile files is not yet implemented.")
+        let nix_source = std::fs::read_to_string(expression).map_err(|err| {
+            NixError::io(
+                expression,
+                format!("Failed to read the file '{expression}'. Error: {err}."),
+            )
+        })?;
+        let source_path = Path::new(expression).canonicalize().map_err(|err| {
+            NixError::io(
+                expression,
+                format!("Failed to resolve the path '{expression}'. Error: {err}."),
+            )
+        })?;
+        (nix_source, source_path.to_string_lossy().into_owned())
+    };
+
+    let (mut js_source, source_map) = emit_js::emit_module_with_source_map(&nix_source)?;
+
+    let Some(output) = parsed_args.get_one::<String>("output") else {
+        print!("{js_source}");
+        return Ok(());
+    };
+
+    if parsed_args.get_flag("source-map") {
+        let js_file_name = Path::new(output)
+            .file_name()
+            .ok_or_else(|| NixError::usage(format!("The output path '{output}' is not a file.")))?
+            .to_string_lossy();
+        let source_map_path = format!("{output}.map");
+        let source_map_json = source_map.to_json(&js_file_name, &source_name, &nix_source);
+        std::fs::write(&source_map_path, source_map_json).map_err(|err| {
+            NixError::io(
+                &source_map_path,
+                format!("Failed to write the source map to '{source_map_path}'. Error: {err}."),
+            )
+        })?;
+        js_source += &format!("//# sourceMappingURL={js_file_name}.map\n");
     }
+
+    std::fs::write(output, js_source).map_err(|err| {
+        NixError::io(
+            output,
+            format!("Failed to write the JavaScript to '{output}'. Error: {err}."),
+        )
+    })?;
     Ok(())
 }
@@ -1,21 +1,100 @@
 use std::collections::HashSet;
+use std::ops::{AddAssign, Deref, DerefMut};
 
-use rnix::{ast, SyntaxKind};
-use rowan::ast::AstNode;
+use rnix::{ast, parser::ParseError, SyntaxKind, SyntaxNode};
+use rowan::{ast::AstNode, TextSize};
 
-pub fn emit_module(nix_expr: &str) -> Result<String, String> {
-    let root = rnix::Root::parse(nix_expr).tree();
-    let root_expr = root.expr().expect("Not implemented");
-    let mut out_src = String::new();
+use super::error::{NixError, NixErrorKind, NixErrorMessagePart};
+use super::modules::{NIXJS_RT_MODULE, ROOT_MODULE_NAME};
+use super::source_map::SourceMap;
+
+pub fn emit_module(nix_expr: &str) -> Result<String, NixError> {
+    Ok(emit_module_with_source_map(nix_expr)?.0)
+}
+
+/// Transpiles the given Nix expression into a JavaScript module and returns
+/// it together with a source map pointing back into the Nix expression.
+pub fn emit_module_with_source_map(nix_expr: &str) -> Result<(String, SourceMap), NixError> {
+    let parse = rnix::Root::parse(nix_expr);
+    if let Some(parse_error) = parse.errors().first() {
+        return Err(rnix_parse_error_to_nix(nix_expr, parse_error));
+    }
+    let root = parse.tree();
+    let root_expr = required(root.expr(), &root, "Unexpected empty expression.")?;
+    let mut out_src = JsOutput::default();
+    out_src += &format!("import * as n from \"{NIXJS_RT_MODULE}\";\n");
     out_src += "export default (ctx) => ";
     emit_expr(&root_expr, &mut out_src)?;
     out_src += ";\n";
-    Ok(out_src)
+    let source_map = SourceMap::from_offsets(&out_src.src, nix_expr, &out_src.offsets);
+    Ok((out_src.src, source_map))
+}
+
+/// Describes the outermost Nix expression that starts at the given byte offset
+/// of the Nix source, like Nix describes the frames of its stack traces.
+pub fn describe_expr_at(nix_source: &str, offset: usize) -> String {
+    let root = rnix::Root::parse(nix_source).tree();
+    let expr = root
+        .syntax()
+        .descendants()
+        .filter(|node| usize::from(node.text_range().start()) == offset)
+        .find_map(ast::Expr::cast);
+    match expr {
+        Some(ast::Expr::Apply(_)) => "from call site".to_owned(),
+        Some(ast::Expr::Assert(_)) => "while evaluating the assertion".to_owned(),
+        Some(ast::Expr::IfElse(_)) => "while evaluating a branch condition".to_owned(),
+        Some(ast::Expr::Select(select)) => match select.attrpath() {
+            Some(attrpath) => format!("while evaluating the attribute '{attrpath}'"),
+            None => "while selecting an attribute".to_owned(),
+        },
+        Some(ast::Expr::With(_)) => {
+            "while evaluating the first subexpression of a with expression".to_owned()
+        }
+        _ => "while evaluating an expression".to_owned(),
+    }
+}
+
+/// The emitted JavaScript source together with the offsets of Nix expressions
+/// in it. Each offset pair is a byte offset into the JavaScript source and the
+/// byte offset of the corresponding Nix expression.
+#[derive(Default)]
+struct JsOutput {
+    src: String,
+    offsets: Vec<(usize, usize)>,
 }
 
-fn emit_expr(nix_ast: &ast::Expr, out_src: &mut String) -> Result<(), String> {
+impl JsOutput {
+    fn add_mapping(&mut self, nix_node: &SyntaxNode) {
+        self.offsets
+            .push((self.src.len(), nix_node.text_range().start().into()));
+    }
+}
+
+impl Deref for JsOutput {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.src
+    }
+}
+
+impl DerefMut for JsOutput {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.src
+    }
+}
+
+impl AddAssign<&str> for JsOutput {
+    fn add_assign(&mut self, rhs: &str) {
+        self.src += rhs;
+    }
+}
+
+fn emit_expr(nix_ast: &ast::Expr, out_src: &mut JsOutput) -> Result<(), NixError> {
+    out_src.add_mapping(nix_ast.syntax());
     match nix_ast {
         ast::Expr::Apply(apply) => emit_apply(apply, out_src),
+        ast::Expr::Assert(assert) => emit_assert(assert, out_src),
         ast::Expr::AttrSet(attrset) => emit_attrset(attrset, out_src),
         ast::Expr::BinOp(bin_op) => emit_bin_op(bin_op, out_src),
         ast::Expr::HasAttr(has_attr) => emit_has_attr(has_attr, out_src),
@@ -31,69 +110,199 @@ fn emit_expr(nix_ast: &ast::Expr, out_src: &mut String) -> Result<(), String> {
         ast::Expr::Str(string) => emit_string_expr(string, out_src),
         ast::Expr::UnaryOp(unary_op) => emit_unary_op(unary_op, out_src),
         ast::Expr::With(with) => emit_with(with, out_src),
-        _ => panic!("emit_expr: not implemented: {:?}", nix_ast),
+        ast::Expr::Error(error) => Err(parse_error_at(error.syntax(), "syntax error")),
+        _ => Err(parse_error_at(
+            nix_ast.syntax(),
+            format!("unsupported expression '{nix_ast}'"),
+        )),
     }
 }
 
-fn emit_apply(apply: &ast::Apply, out_src: &mut String) -> Result<(), String> {
+fn emit_apply(apply: &ast::Apply, out_src: &mut JsOutput) -> Result<(), NixError> {
     emit_expr(
-        &apply
-            .lambda()
-            .expect("Unexpected lambda application without the lambda."),
+        &required(
+            apply.lambda(),
+            apply,
+            "Unexpected lambda application without the lambda.",
+        )?,
         out_src,
     )?;
-    out_src.push_str(".apply(");
+    // Arguments are evaluated lazily, so that e.g. `builtins.tryEval` can
+    // catch the errors thrown by its argument.
+    out_src.push_str(".apply(new n.Lazy(ctx,(ctx) => ");
     emit_expr(
-        &apply
-            .argument()
-            .expect("Unexpected lambda application without arguments."),
+        &required(
+            apply.argument(),
+            apply,
+            "Unexpected lambda application without arguments.",
+        )?,
         out_src,
     )?;
-    out_src.push(')');
+    out_src.push_str("))");
+    Ok(())
+}
+
+fn emit_assert(assert: &ast::Assert, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let condition = required(
+        assert.condition(),
+        assert,
+        "Unexpected 'assert' expression without a condition.",
+    )?;
+    let body = required(
+        assert.body(),
+        assert,
+        "Unexpected 'assert' expression without a body.",
+    )?;
+    *out_src += "n.assertExpr(ctx,";
+    emit_expr(&condition, out_src)?;
+    *out_src += ",`";
+    js_string_escape_into(&condition.syntax().text().to_string(), out_src);
+    *out_src += "`,(ctx) => ";
+    emit_expr(&body, out_src)?;
+    *out_src += ")";
     Ok(())
 }
 
-fn emit_attrset(attrset: &ast::AttrSet, out_src: &mut String) -> Result<(), String> {
+fn emit_attrset(attrset: &ast::AttrSet, out_src: &mut JsOutput) -> Result<(), NixError> {
     emit_has_entry(attrset, attrset.rec_token().is_some(), out_src)
 }
 
 fn emit_has_entry(
     has_entry: &impl ast::HasEntry,
     is_recursive: bool,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     *out_src += "n.";
     *out_src += if is_recursive {
         "recAttrset"
     } else {
         "attrset"
     };
-    *out_src += "(ctx,(ctx) => [";
-    for attrpath_value in has_entry.attrpath_values() {
-        out_src.push('[');
-        let attrpath = attrpath_value.attrpath().expect("Not implemented");
-        let value = &attrpath_value.value().expect("Not implemented");
-        emit_attrpath(&attrpath, out_src)?;
-        *out_src += ",new n.Lazy(ctx,(ctx) => ";
-        emit_expr(value, out_src)?;
-        *out_src += ")],";
+    // Plain `inherit a;` entries look up names in the scope enclosing the
+    // attrset (not in the recursive scope of the attrset itself), so we
+    // capture the enclosing context as `inheritCtx`.
+    let has_plain_inherits = has_entry.inherits().any(|inherit| inherit.from().is_none());
+    *out_src += if has_plain_inherits {
+        "(ctx,((inheritCtx) => (ctx) => ["
+    } else {
+        "(ctx,(ctx) => ["
+    };
+    for entry in has_entry.entries() {
+        match entry {
+            ast::Entry::AttrpathValue(attrpath_value) => {
+                emit_attrpath_value(&attrpath_value, out_src)?
+            }
+            ast::Entry::Inherit(inherit) => emit_inherit(&inherit, out_src)?,
+        }
     }
-    *out_src += "])";
+    *out_src += if has_plain_inherits { "])(ctx))" } else { "])" };
     Ok(())
 }
 
-fn emit_attrpath(attrpath: &ast::Attrpath, out_src: &mut String) -> Result<(), String> {
+fn emit_attrpath_value(
+    attrpath_value: &ast::AttrpathValue,
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
+    out_src.push('[');
+    let attrpath = required(
+        attrpath_value.attrpath(),
+        attrpath_value,
+        "Unexpected attribute without a name.",
+    )?;
+    let value = &required(
+        attrpath_value.value(),
+        attrpath_value,
+        "Unexpected attribute without a value.",
+    )?;
+    emit_attrpath(&attrpath, out_src)?;
+    *out_src += ",new n.Lazy(ctx,(ctx) => ";
+    emit_expr(value, out_src)?;
+    *out_src += ")],";
+    Ok(())
+}
+
+fn emit_inherit(inherit: &ast::Inherit, out_src: &mut JsOutput) -> Result<(), NixError> {
+    match inherit.from() {
+        Some(inherit_from) => {
+            let from_expr = required(
+                inherit_from.expr(),
+                &inherit_from,
+                "Unexpected 'inherit' without a source expression.",
+            )?;
+            // The source expression is shared by all inherited attributes and
+            // is evaluated at most once.
+            *out_src += "...((inheritFrom) => [";
+            for attr in inherit.attrs() {
+                let attr_name = inherited_attr_name(&attr)?;
+                *out_src += "[[";
+                emit_js_nix_string(&attr_name, out_src);
+                *out_src += "],new n.Lazy(ctx,(ctx) => inheritFrom.select([";
+                emit_js_nix_string(&attr_name, out_src);
+                *out_src += "],undefined))],";
+            }
+            *out_src += "])(new n.Lazy(ctx,(ctx) => ";
+            emit_expr(&from_expr, out_src)?;
+            *out_src += ")),";
+        }
+        None => {
+            for attr in inherit.attrs() {
+                let attr_name = inherited_attr_name(&attr)?;
+                *out_src += "[[";
+                emit_js_nix_string(&attr_name, out_src);
+                *out_src += "],new n.Lazy(inheritCtx,(ctx) => ctx.lookup(`";
+                js_string_escape_into(&attr_name, out_src);
+                *out_src += "`))],";
+            }
+        }
+    }
+    Ok(())
+}
+
+fn inherited_attr_name(attr: &ast::Attr) -> Result<String, NixError> {
+    match attr {
+        ast::Attr::Ident(ident) => Ok(required(
+            ident.ident_token(),
+            ident,
+            "Unexpected inherited attribute without a name.",
+        )?
+        .text()
+        .to_owned()),
+        ast::Attr::Str(string) => {
+            let mut attr_name = String::new();
+            for string_part in string.normalized_parts() {
+                match string_part {
+                    ast::InterpolPart::Literal(literal) => attr_name += &literal,
+                    ast::InterpolPart::Interpolation(_) => {
+                        return Err(parse_error_at(
+                            string.syntax(),
+                            "dynamic attributes not allowed in inherit",
+                        ))
+                    }
+                }
+            }
+            Ok(attr_name)
+        }
+        ast::Attr::Dynamic(dynamic) => Err(parse_error_at(
+            dynamic.syntax(),
+            "dynamic attributes not allowed in inherit",
+        )),
+    }
+}
+
+fn emit_attrpath(attrpath: &ast::Attrpath, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "[";
     for attr in attrpath.attrs() {
         out_src.push_str("new n.Lazy(ctx,(ctx) =>");
         match attr {
-            ast::Attr::Ident(ident) => {
-                emit_nix_string(ident.ident_token().expect("Missing token.").text(), out_src)
-            }
+            ast::Attr::Ident(ident) => emit_nix_string(
+                required(ident.ident_token(), &ident, "Missing token.")?.text(),
+                out_src,
+            ),
             ast::Attr::Str(str_expression) => emit_string_expr(&str_expression, out_src)?,
-            ast::Attr::Dynamic(expr) => {
-                emit_expr(&expr.expr().expect("Expected an expression."), out_src)?
-            }
+            ast::Attr::Dynamic(expr) => emit_expr(
+                &required(expr.expr(), &expr, "Expected an expression.")?,
+                out_src,
+            )?,
         }
         out_src.push_str("),");
     }
@@ -107,10 +316,16 @@ fn emit_nix_string(string: &str, out_src: &mut String) {
     *out_src += "\")";
 }
 
-fn emit_bin_op(bin_op: &ast::BinOp, out_src: &mut String) -> Result<(), String> {
-    let operator = bin_op.operator().expect("Not implemented");
-    let lhs = &bin_op.lhs().expect("Not implemented");
-    let rhs = &bin_op.rhs().expect("Not implemented");
+fn emit_js_nix_string(string: &str, out_src: &mut String) {
+    *out_src += "new n.NixString(`";
+    js_string_escape_into(string, out_src);
+    *out_src += "`)";
+}
+
+fn emit_bin_op(bin_op: &ast::BinOp, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let operator = required(bin_op.operator(), bin_op, "Unexpected missing operator.")?;
+    let lhs = &required(bin_op.lhs(), bin_op, "Unexpected missing left operand.")?;
+    let rhs = &required(bin_op.rhs(), bin_op, "Unexpected missing right operand.")?;
     match operator {
         // Arithmetic
         ast::BinOpKind::Add => emit_nixrt_bin_op(lhs, rhs, "add", out_src)?,
@@ -144,8 +359,8 @@ fn emit_nixrt_bin_op(
     lhs: &ast::Expr,
     rhs: &ast::Expr,
     nixrt_function: &str,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     emit_expr(lhs, out_src)?;
     out_src.push('.');
     *out_src += nixrt_function;
@@ -155,8 +370,8 @@ fn emit_nixrt_bin_op(
     Ok(())
 }
 
-fn emit_ident(ident: &ast::Ident, out_src: &mut String) -> Result<(), String> {
-    let token = ident.ident_token().expect("Unexpected ident without name.");
+fn emit_ident(ident: &ast::Ident, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let token = required(ident.ident_token(), ident, "Unexpected ident without name.")?;
     let token_text = token.text();
     match token_text {
         "true" => out_src.push_str("n.TRUE"),
@@ -171,24 +386,40 @@ fn emit_ident(ident: &ast::Ident, out_src: &mut String) -> Result<(), String> {
     Ok(())
 }
 
-fn emit_has_attr(has_attr: &ast::HasAttr, out_src: &mut String) -> Result<(), String> {
-    emit_expr(&has_attr.expr().expect("Unreachable"), out_src)?;
+fn emit_has_attr(has_attr: &ast::HasAttr, out_src: &mut JsOutput) -> Result<(), NixError> {
+    emit_expr(
+        &required(has_attr.expr(), has_attr, "Unexpected '?' without a value.")?,
+        out_src,
+    )?;
     *out_src += ".has(";
-    emit_attrpath(&has_attr.attrpath().expect("Unreachable"), out_src)?;
+    emit_attrpath(
+        &required(
+            has_attr.attrpath(),
+            has_attr,
+            "Unexpected '?' without an attribute path.",
+        )?,
+        out_src,
+    )?;
     *out_src += ")";
     Ok(())
 }
 
-fn emit_if_else(lambda: &ast::IfElse, out_src: &mut String) -> Result<(), String> {
-    let condition = lambda
-        .condition()
-        .expect("Unexpected 'if-then-else' expression without a condition.");
-    let body = lambda
-        .body()
-        .expect("Unexpected 'if-then-else' expression without a body.");
-    let else_body = lambda
-        .else_body()
-        .expect("Unexpected 'if-then-else' expression without an 'else' body.");
+fn emit_if_else(lambda: &ast::IfElse, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let condition = required(
+        lambda.condition(),
+        lambda,
+        "Unexpected 'if-then-else' expression without a condition.",
+    )?;
+    let body = required(
+        lambda.body(),
+        lambda,
+        "Unexpected 'if-then-else' expression without a body.",
+    )?;
+    let else_body = required(
+        lambda.else_body(),
+        lambda,
+        "Unexpected 'if-then-else' expression without an 'else' body.",
+    )?;
     emit_expr(&condition, out_src)?;
     *out_src += ".asBoolean() ? (";
     emit_expr(&body, out_src)?;
@@ -198,13 +429,13 @@ fn emit_if_else(lambda: &ast::IfElse, out_src: &mut String) -> Result<(), String
     Ok(())
 }
 
-fn emit_lambda(lambda: &ast::Lambda, out_src: &mut String) -> Result<(), String> {
-    let param = lambda
-        .param()
-        .expect("Unexpected lambda without parameters.");
-    let body = lambda
-        .body()
-        .expect("Unexpected lambda without parameters.");
+fn emit_lambda(lambda: &ast::Lambda, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let param = required(
+        lambda.param(),
+        lambda,
+        "Unexpected lambda without parameters.",
+    )?;
+    let body = required(lambda.body(), lambda, "Unexpected lambda without a body.")?;
     match param {
         ast::Param::IdentParam(ident_param) => emit_param_lambda(&ident_param, &body, out_src),
         ast::Param::Pattern(pattern) => emit_pattern_lambda(&pattern, &body, out_src),
@@ -214,13 +445,15 @@ fn emit_lambda(lambda: &ast::Lambda, out_src: &mut String) -> Result<(), String>
 fn emit_param_lambda(
     ident_param: &ast::IdentParam,
     body: &ast::Expr,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     *out_src += "n.paramLambda(ctx,";
     emit_ident_as_js_string(
-        &ident_param
-            .ident()
-            .expect("Unexpected missing lambda parameter identifier."),
+        &required(
+            ident_param.ident(),
+            ident_param,
+            "Unexpected missing lambda parameter identifier.",
+        )?,
         out_src,
     );
     *out_src += ",(ctx) => ";
@@ -232,8 +465,8 @@ fn emit_param_lambda(
 fn emit_pattern_lambda(
     pattern: &ast::Pattern,
     body: &ast::Expr,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     let mut formal_arg_names = HashSet::new();
     *out_src += "n.patternLambda(ctx,";
     if let Some(indent) = pattern.pat_bind().and_then(|pat_bind| pat_bind.ident()) {
@@ -244,11 +477,16 @@ fn emit_pattern_lambda(
     }
     *out_src += ",[";
     for pattern_entry in pattern.pat_entries() {
-        let ident = pattern_entry.ident().ok_or_else(|| {
-            "Unsupported lambda pattern parameter without an identifier.".to_owned()
-        })?;
+        let ident = required(
+            pattern_entry.ident(),
+            &pattern_entry,
+            "Unsupported lambda pattern parameter without an identifier.",
+        )?;
         if !formal_arg_names.insert(ident.to_string()) {
-            return Err(format!("duplicate formal function argument '{}'.", ident));
+            return Err(parse_error_at(
+                pattern_entry.syntax(),
+                format!("duplicate formal function argument '{ident}'"),
+            ));
         }
         *out_src += "[";
         emit_ident_as_js_string(&ident, out_src);
@@ -270,21 +508,23 @@ fn emit_ident_as_js_string(ident: &ast::Ident, out_src: &mut String) {
     out_src.push('"');
 }
 
-fn emit_let_in(let_in: &ast::LetIn, out_src: &mut String) -> Result<(), String> {
+fn emit_let_in(let_in: &ast::LetIn, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "n.letIn(ctx,";
     emit_has_entry(let_in, true, out_src)?;
     *out_src += ",(ctx) => ";
     emit_expr(
-        &let_in
-            .body()
-            .expect("Unexpected let-in expression without a body."),
+        &required(
+            let_in.body(),
+            let_in,
+            "Unexpected let-in expression without a body.",
+        )?,
         out_src,
     )?;
     *out_src += ")";
     Ok(())
 }
 
-fn emit_list(list: &ast::List, out_src: &mut String) -> Result<(), String> {
+fn emit_list(list: &ast::List, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "new n.NixList([";
     for element in list.items() {
         out_src.push_str("new n.Lazy(ctx,(ctx) => ");
@@ -295,8 +535,12 @@ fn emit_list(list: &ast::List, out_src: &mut String) -> Result<(), String> {
     Ok(())
 }
 
-fn emit_literal(literal: &ast::Literal, out_src: &mut String) -> Result<(), String> {
-    let token = literal.syntax().first_token().expect("Not implemented");
+fn emit_literal(literal: &ast::Literal, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let token = required(
+        literal.syntax().first_token(),
+        literal,
+        "Unexpected empty literal.",
+    )?;
     match token.kind() {
         SyntaxKind::TOKEN_INTEGER => {
             out_src.push_str("new n.NixInt(");
@@ -309,32 +553,53 @@ fn emit_literal(literal: &ast::Literal, out_src: &mut String) -> Result<(), Stri
             out_src.push(')');
         }
         SyntaxKind::TOKEN_URI => emit_nix_string(token.text(), out_src),
-        _ => todo!("emit_literal: {:?} token kind: {:?}", literal, token.kind()),
+        _ => {
+            return Err(parse_error_at(
+                literal.syntax(),
+                format!("unsupported literal '{literal}'"),
+            ))
+        }
     }
     Ok(())
 }
 
-fn emit_paren(paren: &ast::Paren, out_src: &mut String) -> Result<(), String> {
+fn emit_paren(paren: &ast::Paren, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "(";
-    let body = paren
-        .expr()
-        .expect("Unexpected parenthesis without a body.");
+    let body = required(
+        paren.expr(),
+        paren,
+        "Unexpected parenthesis without a body.",
+    )?;
     emit_expr(&body, out_src)?;
     *out_src += ")";
     Ok(())
 }
 
-fn emit_path(path: &ast::Path, out_src: &mut String) -> Result<(), String> {
+fn emit_path(path: &ast::Path, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "n.toPath(ctx,`";
     js_string_escape_into(&path.to_string(), out_src);
     *out_src += "`)";
     Ok(())
 }
 
-fn emit_select_expr(select: &ast::Select, out_src: &mut String) -> Result<(), String> {
-    emit_expr(&select.expr().expect("Unreachable"), out_src)?;
+fn emit_select_expr(select: &ast::Select, out_src: &mut JsOutput) -> Result<(), NixError> {
+    emit_expr(
+        &required(
+            select.expr(),
+            select,
+            "Unexpected selection without a value.",
+        )?,
+        out_src,
+    )?;
     *out_src += ".select(";
-    emit_attrpath(&select.attrpath().expect("Unreachable"), out_src)?;
+    emit_attrpath(
+        &required(
+            select.attrpath(),
+            select,
+            "Unexpected selection without an attribute path.",
+        )?,
+        out_src,
+    )?;
     *out_src += ",";
     match select.default_expr() {
         Some(default_expr) => emit_expr(&default_expr, out_src)?,
@@ -344,40 +609,64 @@ fn emit_select_expr(select: &ast::Select, out_src: &mut String) -> Result<(), St
     Ok(())
 }
 
-fn emit_string_expr(string: &ast::Str, out_src: &mut String) -> Result<(), String> {
-    *out_src += "new n.NixString(`";
-    for string_part in string.normalized_parts() {
+fn emit_string_expr(string: &ast::Str, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let parts = string.normalized_parts();
+    if parts
+        .iter()
+        .all(|part| matches!(part, ast::InterpolPart::Literal(_)))
+    {
+        *out_src += "new n.NixString(`";
+        for string_part in parts {
+            if let ast::InterpolPart::Literal(literal) = string_part {
+                js_string_escape_into(&literal, out_src);
+            }
+        }
+        *out_src += "`)";
+        return Ok(());
+    }
+
+    // Interpolated strings go through the runtime, which tracks the string
+    // context of the interpolated values.
+    *out_src += "n.interpolate([";
+    for string_part in parts {
         match string_part {
             ast::InterpolPart::Literal(literal) => {
+                *out_src += "`";
                 js_string_escape_into(&literal, out_src);
+                *out_src += "`";
             }
             ast::InterpolPart::Interpolation(interpolation_body) => {
-                *out_src += "${";
                 emit_expr(
-                    &interpolation_body
-                        .expr()
-                        .expect("String interpolation body missing."),
+                    &required(
+                        interpolation_body.expr(),
+                        &interpolation_body,
+                        "String interpolation body missing.",
+                    )?,
                     out_src,
                 )?;
-                *out_src += ".asString()}";
             }
         }
+        *out_src += ",";
     }
-    *out_src += "`)";
+    *out_src += "])";
     Ok(())
 }
 
-fn emit_unary_op(unary_op: &ast::UnaryOp, out_src: &mut String) -> Result<(), String> {
-    let operator = unary_op.operator().expect("Not implemented");
-    let operand = unary_op.expr().expect("Not implemented");
+fn emit_unary_op(unary_op: &ast::UnaryOp, out_src: &mut JsOutput) -> Result<(), NixError> {
+    let operator = required(
+        unary_op.operator(),
+        unary_op,
+        "Unexpected missing operator.",
+    )?;
+    let operand = required(unary_op.expr(), unary_op, "Unexpected missing operand.")?;
     emit_unary_op_kind(operator, &operand, out_src)
 }
 
 fn emit_unary_op_kind(
     operator: ast::UnaryOpKind,
     operand: &ast::Expr,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     match operator {
         ast::UnaryOpKind::Invert => emit_nixrt_unary_op(operand, "invert", out_src),
         ast::UnaryOpKind::Negate => emit_nixrt_unary_op(operand, "neg", out_src),
@@ -387,8 +676,8 @@ fn emit_unary_op_kind(
 fn emit_nixrt_unary_op(
     operand: &ast::Expr,
     nixrt_function: &str,
-    out_src: &mut String,
-) -> Result<(), String> {
+    out_src: &mut JsOutput,
+) -> Result<(), NixError> {
     emit_expr(operand, out_src)?;
     out_src.push('.');
     *out_src += nixrt_function;
@@ -396,19 +685,23 @@ fn emit_nixrt_unary_op(
     Ok(())
 }
 
-fn emit_with(with: &ast::With, out_src: &mut String) -> Result<(), String> {
+fn emit_with(with: &ast::With, out_src: &mut JsOutput) -> Result<(), NixError> {
     *out_src += "n.withExpr(ctx,";
     emit_expr(
-        &with
-            .namespace()
-            .ok_or_else(|| "Unexpected 'with' expression without a namespace.".to_string())?,
+        &required(
+            with.namespace(),
+            with,
+            "Unexpected 'with' expression without a namespace.",
+        )?,
         out_src,
     )?;
     *out_src += ",(ctx) => ";
     emit_expr(
-        &with
-            .body()
-            .ok_or_else(|| "Unexpected 'with' expression without a body.".to_string())?,
+        &required(
+            with.body(),
+            with,
+            "Unexpected 'with' expression without a body.",
+        )?,
         out_src,
     )?;
     *out_src += ")";
@@ -426,3 +719,113 @@ fn js_string_escape_into(string: &str, out_string: &mut String) {
         }
     }
 }
+
+/// Returns the given child node, or a parse error located at the parent node
+/// if the child is missing.
+fn required<T>(
+    child: Option<T>,
+    parent: &impl AstNode<Language = rnix::NixLanguage>,
+    message: &str,
+) -> Result<T, NixError> {
+    child.ok_or_else(|| parse_error_at(parent.syntax(), message))
+}
+
+fn parse_error_at(node: &SyntaxNode, message: impl Into<String>) -> NixError {
+    let root = node.ancestors().last().unwrap_or_else(|| node.clone());
+    parse_error(
+        &root.text().to_string(),
+        node.text_range().start(),
+        message.into(),
+    )
+}
+
+fn rnix_parse_error_to_nix(nix_expr: &str, rnix_error: &ParseError) -> NixError {
+    let end = TextSize::of(nix_expr);
+    let (offset, message) = match rnix_error {
+        ParseError::Unexpected(range) | ParseError::UnexpectedExtra(range) => {
+            (range.start(), format!("unexpected '{}'", &nix_expr[*range]))
+        }
+        ParseError::UnexpectedWanted(_, range, wanted) => (
+            range.start(),
+            format!(
+                "unexpected '{}', expecting {}",
+                &nix_expr[*range],
+                describe_syntax_kinds(wanted)
+            ),
+        ),
+        ParseError::UnexpectedDoubleBind(range) => {
+            (range.start(), "unexpected double bind".to_owned())
+        }
+        ParseError::UnexpectedEOF => (end, "unexpected end of file".to_owned()),
+        ParseError::UnexpectedEOFWanted(wanted) => (
+            end,
+            format!(
+                "unexpected end of file, expecting {}",
+                describe_syntax_kinds(wanted)
+            ),
+        ),
+        ParseError::DuplicatedArgs(range, ident) => (
+            range.start(),
+            format!("duplicate formal function argument '{ident}'"),
+        ),
+        rnix_error => (0.into(), rnix_error.to_string()),
+    };
+    parse_error(nix_expr, offset, format!("syntax error, {message}"))
+}
+
+fn describe_syntax_kinds(kinds: &[SyntaxKind]) -> String {
+    kinds
+        .iter()
+        .map(describe_syntax_kind)
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+fn describe_syntax_kind(kind: &SyntaxKind) -> String {
+    let token = match kind {
+        SyntaxKind::TOKEN_L_BRACE => "{",
+        SyntaxKind::TOKEN_R_BRACE => "}",
+        SyntaxKind::TOKEN_L_BRACK => "[",
+        SyntaxKind::TOKEN_R_BRACK => "]",
+        SyntaxKind::TOKEN_L_PAREN => "(",
+        SyntaxKind::TOKEN_R_PAREN => ")",
+        SyntaxKind::TOKEN_ASSIGN => "=",
+        SyntaxKind::TOKEN_AT => "@",
+        SyntaxKind::TOKEN_COLON => ":",
+        SyntaxKind::TOKEN_COMMA => ",",
+        SyntaxKind::TOKEN_DOT => ".",
+        SyntaxKind::TOKEN_QUESTION => "?",
+        SyntaxKind::TOKEN_SEMICOLON => ";",
+        SyntaxKind::TOKEN_INTERPOL_START => "${",
+        SyntaxKind::TOKEN_INTERPOL_END => "}",
+        SyntaxKind::TOKEN_STRING_START | SyntaxKind::TOKEN_STRING_END => "\"",
+        _ => {
+            return format!("{kind:?}")
+                .trim_start_matches("TOKEN_")
+                .trim_start_matches("NODE_")
+                .replace('_', " ")
+                .to_lowercase()
+        }
+    };
+    format!("'{token}'")
+}
+
+fn parse_error(nix_expr: &str, offset: TextSize, message: String) -> NixError {
+    let preceding_src = &nix_expr[..usize::from(offset)];
+    let line = preceding_src.matches('\n').count() + 1;
+    let column = preceding_src
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line_prefix| line_prefix.chars().count())
+        + 1;
+    NixError {
+        message: vec![NixErrorMessagePart::Plain(message.clone())],
+        kind: NixErrorKind::ParseError {
+            file: ROOT_MODULE_NAME.to_owned(),
+            line,
+            column,
+            message,
+        },
+        trace: Vec::new(),
+    }
+}
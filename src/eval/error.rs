@@ -2,6 +2,7 @@ use deno_core::v8;
 
 use super::{
     helpers::{call_js_function, get_js_value_key, is_nixrt_type},
+    modules::nix_stack_trace,
     types::NixTypeKind,
 };
 
@@ -9,6 +10,21 @@ use super::{
 pub struct NixError {
     pub message: Vec<NixErrorMessagePart>,
     pub kind: NixErrorKind,
+    /// The Nix expressions that were being evaluated when the error occurred,
+    /// starting with the outermost one.
+    pub trace: Vec<NixStackFrame>,
+}
+
+/// A position in Nix code that was being evaluated when an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NixStackFrame {
+    /// The path of the Nix file, or `«string»` for the evaluated expression.
+    pub file: String,
+    /// The one-based line.
+    pub line: usize,
+    /// The one-based column.
+    pub column: usize,
+    pub description: String,
 }
 
 impl std::fmt::Display for NixError {
@@ -24,11 +40,44 @@ impl std::fmt::Display for NixError {
     }
 }
 
+impl NixError {
+    /// An error about a file that couldn't be read or resolved.
+    pub fn io(path: &str, message: String) -> Self {
+        NixError {
+            message: vec![NixErrorMessagePart::Plain(message.clone())],
+            kind: NixErrorKind::Io {
+                path: path.to_owned(),
+                message,
+            },
+            trace: Vec::new(),
+        }
+    }
+
+    /// An error about invalid command line arguments.
+    pub fn usage(message: String) -> Self {
+        NixError {
+            message: vec![NixErrorMessagePart::Plain(message.clone())],
+            kind: NixErrorKind::Usage { message },
+            trace: Vec::new(),
+        }
+    }
+
+    /// Attributes a parse error to the given file rather than to the
+    /// evaluated expression. Other errors are returned unchanged.
+    pub fn in_file(mut self, file_name: &str) -> Self {
+        if let NixErrorKind::ParseError { file, .. } = &mut self.kind {
+            file_name.clone_into(file);
+        }
+        self
+    }
+}
+
 impl From<String> for NixError {
     fn from(message: String) -> Self {
         NixError {
             message: vec![NixErrorMessagePart::Plain(message.clone())],
             kind: NixErrorKind::UnexpectedRustError { message },
+            trace: Vec::new(),
         }
     }
 }
@@ -40,6 +89,7 @@ impl<'a> From<&'a str> for NixError {
             kind: NixErrorKind::UnexpectedRustError {
                 message: message.to_string(),
             },
+            trace: Vec::new(),
         }
     }
 }
@@ -51,6 +101,7 @@ impl From<v8::DataError> for NixError {
             kind: NixErrorKind::UnexpectedJsError {
                 message: error.to_string(),
             },
+            trace: Vec::new(),
         }
     }
 }
@@ -66,6 +117,9 @@ pub enum NixErrorKind {
     Abort {
         message: String,
     },
+    AssertionFailed {
+        condition: String,
+    },
     CouldntFindVariable {
         var_name: String,
     },
@@ -85,6 +139,30 @@ pub enum NixErrorKind {
     FunctionCallWithoutArgument {
         argument: String,
     },
+    ImportFailed {
+        path: String,
+        reason: String,
+    },
+    ParseError {
+        /// The path of the Nix file, or `«string»` for the evaluated expression.
+        file: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Io {
+        path: String,
+        message: String,
+    },
+    /// The path is outside of the allowed paths in restricted or pure mode.
+    AccessForbidden {
+        path: String,
+    },
+
+    /// Invalid command line arguments that `clap` doesn't catch.
+    Usage {
+        message: String,
+    },
 
     // For non-nix errors thrown in js or rust
     UnexpectedJsError {
@@ -102,10 +180,12 @@ pub fn js_error_to_rust(
 ) -> NixError {
     let result = try_js_error_to_rust(scope, nixrt, error);
 
-    match result {
+    let mut nix_error = match result {
         Ok(ok) => ok,
         Err(err) => err,
-    }
+    };
+    nix_error.trace = nix_stack_trace(scope, error);
+    nix_error
 }
 
 fn try_js_error_to_rust(
@@ -120,6 +200,7 @@ fn try_js_error_to_rust(
             kind: NixErrorKind::UnexpectedJsError {
                 message: error.to_rust_string_lossy(scope),
             },
+            trace: Vec::new(),
         });
     }
 
@@ -137,6 +218,11 @@ fn try_js_error_to_rust(
             let message = message_js.to_rust_string_lossy(scope);
             NixErrorKind::Abort { message }
         }
+        "NixAssertionFailedError" => {
+            let condition_js = get_js_value_key(scope, &kind_js, "condition")?;
+            let condition = condition_js.to_rust_string_lossy(scope);
+            NixErrorKind::AssertionFailed { condition }
+        }
         "NixCouldntFindVariableError" => {
             let var_name_js = get_js_value_key(scope, &kind_js, "varName")?;
             let var_name = var_name_js.to_rust_string_lossy(scope);
@@ -175,6 +261,41 @@ fn try_js_error_to_rust(
             let argument = argument_js.to_rust_string_lossy(scope);
             NixErrorKind::FunctionCallWithoutArgument { argument }
         }
+        "NixImportFailedError" => {
+            let path_js = get_js_value_key(scope, &kind_js, "path")?;
+            let path = path_js.to_rust_string_lossy(scope);
+            let reason_js = get_js_value_key(scope, &kind_js, "reason")?;
+            let reason = reason_js.to_rust_string_lossy(scope);
+            NixErrorKind::ImportFailed { path, reason }
+        }
+        "NixIoError" => {
+            let path_js = get_js_value_key(scope, &kind_js, "path")?;
+            let path = path_js.to_rust_string_lossy(scope);
+            let message_js = get_js_value_key(scope, &kind_js, "message")?;
+            let message = message_js.to_rust_string_lossy(scope);
+            NixErrorKind::Io { path, message }
+        }
+        "NixParseError" => {
+            let file_js = get_js_value_key(scope, &kind_js, "file")?;
+            let file = file_js.to_rust_string_lossy(scope);
+            let line_js = get_js_value_key(scope, &kind_js, "line")?;
+            let line = js_number_to_usize(scope, line_js)?;
+            let column_js = get_js_value_key(scope, &kind_js, "column")?;
+            let column = js_number_to_usize(scope, column_js)?;
+            let message_js = get_js_value_key(scope, &kind_js, "message")?;
+            let message = message_js.to_rust_string_lossy(scope);
+            NixErrorKind::ParseError {
+                file,
+                line,
+                column,
+                message,
+            }
+        }
+        "NixAccessForbiddenError" => {
+            let path_js = get_js_value_key(scope, &kind_js, "path")?;
+            let path = path_js.to_rust_string_lossy(scope);
+            NixErrorKind::AccessForbidden { path }
+        }
         _ => {
             return Ok(NixError {
                 message: vec![NixErrorMessagePart::Plain(
@@ -183,11 +304,16 @@ fn try_js_error_to_rust(
                 kind: NixErrorKind::UnexpectedJsError {
                     message: error.to_rust_string_lossy(scope),
                 },
+                trace: Vec::new(),
             });
         }
     };
 
-    Ok(NixError { message, kind })
+    Ok(NixError {
+        message,
+        kind,
+        trace: Vec::new(),
+    })
 }
 
 fn nix_type_class_to_enum(
@@ -216,6 +342,7 @@ fn nix_type_class_to_enum(
             kind: NixErrorKind::Other {
                 codename: "unknown-type".to_owned(),
             },
+            trace: Vec::new(),
         }),
     }
 }
@@ -269,6 +396,14 @@ fn js_string_array_to_rust_string_array(
     Ok(result)
 }
 
+fn js_number_to_usize(
+    scope: &mut v8::HandleScope,
+    js_number: v8::Local<v8::Value>,
+) -> Result<usize, NixError> {
+    let number = js_number.integer_value(scope).ok_or("Expected a number.")?;
+    Ok(number as usize)
+}
+
 fn js_error_message_part_to_rust(
     scope: &mut v8::HandleScope,
     error_part: v8::Local<v8::Value>,
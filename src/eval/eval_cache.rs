@@ -0,0 +1,141 @@
+//! A persistent cache of transpiled Nix files.
+//!
+//! For every Nix source the cache stores the emitted JavaScript, its source map
+//! and V8's code cache of the compiled module, so that unchanged files are
+//! neither parsed nor compiled again. Entries are keyed by a hash of the Nix
+//! source, the emitter and the runtime, and the code caches additionally by the
+//! V8 version.
+//!
+//! Entries are never evicted, so the cache grows without limit. Entries of
+//! older builds are never read again and deleting the cache directory is
+//! always safe.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use deno_core::v8;
+use sha2::{Digest, Sha256};
+
+use super::source_map::SourceMap;
+
+const JS_EXTENSION: &str = "js";
+const SOURCE_MAP_EXTENSION: &str = "map";
+const CODE_CACHE_EXTENSION: &str = "v8cache";
+
+/// Distinguishes the temporary files of concurrent writes in this process.
+static NEXT_WRITE_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug)]
+pub struct EvalCache {
+    dir: PathBuf,
+}
+
+/// A Nix source whose transpiled JavaScript is cached.
+#[derive(Debug, PartialEq)]
+pub struct CachedModule {
+    pub js_source: String,
+    pub source_map: SourceMap,
+    /// V8's code cache of the compiled module, if there is one.
+    pub code_cache: Option<Vec<u8>>,
+}
+
+impl EvalCache {
+    /// Creates a cache in the given directory.
+    pub fn new(dir: PathBuf) -> Self {
+        EvalCache { dir }
+    }
+
+    /// Creates a cache in `$XDG_CACHE_HOME/rix`, or in `$HOME/.cache/rix` if
+    /// `XDG_CACHE_HOME` is not set. Returns `None` if neither is set.
+    pub fn in_user_cache_dir() -> Option<Self> {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(Self::new(cache_home.join("rix")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the cached JavaScript, source map and code cache of the given
+    /// Nix source.
+    pub fn get(&self, nix_source: &str) -> Option<CachedModule> {
+        let key = cache_key(nix_source);
+        let js_source = std::fs::read_to_string(self.entry_path(&key, JS_EXTENSION)).ok()?;
+        let mappings = std::fs::read_to_string(self.entry_path(&key, SOURCE_MAP_EXTENSION)).ok()?;
+        let source_map = SourceMap::decode_mappings(&mappings)?;
+        let code_cache = std::fs::read(self.entry_path(&key, CODE_CACHE_EXTENSION)).ok();
+        Some(CachedModule {
+            js_source,
+            source_map,
+            code_cache,
+        })
+    }
+
+    /// Stores the JavaScript transpiled from the given Nix source and its
+    /// source map. Failures are ignored because the cache only speeds up
+    /// evaluation.
+    pub fn set_js_source(&self, nix_source: &str, js_source: &str, source_map: &SourceMap) {
+        let key = cache_key(nix_source);
+        let mappings = source_map.encode_mappings();
+        self.write_entry(&key, SOURCE_MAP_EXTENSION, mappings.as_bytes());
+        self.write_entry(&key, JS_EXTENSION, js_source.as_bytes());
+    }
+
+    /// Stores V8's code cache of the module compiled from the given Nix source.
+    pub fn set_code_cache(&self, nix_source: &str, code_cache: &[u8]) {
+        let key = cache_key(nix_source);
+        self.write_entry(&key, CODE_CACHE_EXTENSION, code_cache);
+    }
+
+    /// Removes the cached JavaScript, source map and code cache of the given
+    /// Nix source, e.g. because they're corrupt.
+    pub fn remove(&self, nix_source: &str) {
+        let key = cache_key(nix_source);
+        for extension in [JS_EXTENSION, SOURCE_MAP_EXTENSION, CODE_CACHE_EXTENSION] {
+            let _ = std::fs::remove_file(self.entry_path(&key, extension));
+        }
+    }
+
+    fn entry_path(&self, key: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{extension}"))
+    }
+
+    /// Writes the entry into a temporary file first, so that concurrent
+    /// evaluations never read a partially written entry. Every write has its
+    /// own temporary file, also between evaluators in the same process.
+    fn write_entry(&self, key: &str, extension: &str, contents: &[u8]) {
+        if std::fs::create_dir_all(&self.dir).is_err() {
+            return;
+        }
+        let write_id = NEXT_WRITE_ID.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = self.entry_path(key, &format!("{}-{write_id}.tmp", std::process::id()));
+        if std::fs::write(&tmp_path, contents).is_err()
+            || std::fs::rename(&tmp_path, self.entry_path(key, extension)).is_err()
+        {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+    }
+}
+
+/// Hashes the Nix source together with the build of the emitter and runtime
+/// (see `build.rs`) and the V8 version, which determine the emitted JavaScript
+/// and the format of the code cache.
+pub fn cache_key(nix_source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(env!("RIX_BUILD_HASH"));
+    hasher.update([0]);
+    hasher.update(v8::script_compiler::cached_data_version_tag().to_le_bytes());
+    hasher.update([0]);
+    hasher.update(nix_source);
+    hasher
+        .finalize()
+        .iter()
+        .fold(String::with_capacity(64), |mut key, byte| {
+            let _ = write!(key, "{byte:02x}");
+            key
+        })
+}
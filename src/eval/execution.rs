@@ -1,22 +1,211 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use deno_core::v8;
-use deno_core::v8::{HandleScope, Local, ModuleStatus, Object};
+use deno_core::v8::{HandleScope, ModuleStatus};
 
 use crate::eval::types::EvalResult;
 
-use super::emit_js::emit_module;
-use super::error::NixError;
-use super::helpers::{call_js_function, get_nixrt_type, try_get_js_object_key};
+use super::error::{js_error_to_rust, NixError, NixErrorKind};
+use super::eval_cache::EvalCache;
+use super::fs::{get_fs, set_fs, NixFs};
+use super::hash::{Hash, HashAlgo, HashFormat};
+use super::helpers::{
+    call_js_function, call_js_instance_mehod, get_nixrt_type, try_get_js_object_key,
+};
+use super::modules::{
+    compile_nix_module, forget_nix_modules, get_imported_file, get_nix_module_source,
+    register_nixjs_rt_module, resolve_module, resolve_module_callback, set_imported_file,
+    ImportedFile, ROOT_MODULE_NAME,
+};
+use super::nixjs_rt::{get_nixjs_rt, load_nixjs_rt};
+use super::restrictions::{get_restrictions, set_restrictions, EvalRestrictions};
 use super::types::js_value_to_nix;
 
+/// Evaluates the given expression in a fresh evaluator. Use an [`Evaluator`]
+/// to evaluate many expressions without setting up V8 every time.
 pub fn evaluate(nix_expr: &str, workdir: &Path) -> EvalResult {
-    deno_core::JsRuntime::init_platform(None);
-    // Declare the V8 execution context
-    let isolate = &mut v8::Isolate::new(Default::default());
-    let scope = &mut v8::HandleScope::new(isolate);
-    let context = v8::Context::new(scope);
-    let scope = &mut v8::ContextScope::new(scope, context);
+    Evaluator::new()?.evaluate(nix_expr, workdir)
+}
+
+/// Evaluates the given expression and selects the value at the given
+/// attribute path. Only the selected value is forced.
+pub fn evaluate_attr_path(nix_expr: &str, workdir: &Path, attr_path: &[String]) -> EvalResult {
+    Evaluator::new()?.evaluate_attr_path(nix_expr, workdir, attr_path)
+}
+
+/// The V8 startup snapshot created by the build script. It contains a context
+/// with the Nix runtime already loaded into the global variable `n`. It is
+/// empty if the build script didn't create one, e.g. when cross-compiling.
+static NIXJS_RT_SNAPSHOT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/nixjs_rt.snap"));
+
+static NIXJS_RT_SRC: &str = include_str!("../../nixjs-rt/dist/lib.mjs");
+
+/// The maximum number of JavaScript frames captured for Nix stack traces.
+const MAX_STACK_TRACE_FRAMES: i32 = 256;
+
+/// The version of Nix whose language `rix` implements, as returned by
+/// `builtins.nixVersion`.
+pub const NIX_VERSION: &str = "2.18.1";
+
+/// The version of the Nix language, as returned by `builtins.langVersion`.
+pub const LANG_VERSION: u32 = 6;
+
+/// The Nix system type of the target that `rix` was built for, e.g.
+/// `x86_64-linux`.
+pub fn default_system() -> String {
+    let arch = match std::env::consts::ARCH {
+        "x86" => "i686",
+        "arm" => "armv7l",
+        arch => arch,
+    };
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    format!("{arch}-{os}")
+}
+
+/// Owns a V8 isolate with the Nix runtime already loaded into it, so that it
+/// can evaluate many expressions.
+pub struct Evaluator {
+    // The global handles must be dropped before the isolate.
+    context: v8::Global<v8::Context>,
+    nixrt: v8::Global<v8::Object>,
+    isolate: v8::OwnedIsolate,
+    /// The value of `builtins.currentSystem`.
+    system: String,
+}
+
+impl Evaluator {
+    /// Creates an evaluator that boots from the startup snapshot, or from the
+    /// source of the Nix runtime if `rix` was built without a snapshot.
+    pub fn new() -> Result<Self, NixError> {
+        if NIXJS_RT_SNAPSHOT.is_empty() {
+            return Self::new_without_snapshot();
+        }
+        let create_params = v8::CreateParams::default().snapshot_blob(NIXJS_RT_SNAPSHOT);
+        Self::boot(create_params, None)
+    }
+
+    /// Creates an evaluator that loads the Nix runtime from its source rather
+    /// than from the startup snapshot. This is much slower than [`Evaluator::new`].
+    pub fn new_without_snapshot() -> Result<Self, NixError> {
+        Self::boot(Default::default(), Some(NIXJS_RT_SRC))
+    }
+
+    /// Creates the isolate and its context. The Nix runtime is loaded from the
+    /// given source or, if there is none, it is expected to be in the snapshot.
+    fn boot(create_params: v8::CreateParams, nixjs_rt_src: Option<&str>) -> Result<Self, NixError> {
+        deno_core::JsRuntime::init_platform(None);
+        let mut isolate = v8::Isolate::new(create_params);
+        isolate.set_capture_stack_trace_for_uncaught_exceptions(true, MAX_STACK_TRACE_FRAMES);
+        let (context, nixrt) = {
+            let scope = &mut v8::HandleScope::new(&mut isolate);
+            let context = v8::Context::new(scope);
+            let scope = &mut v8::ContextScope::new(scope, context);
+            insert_host_functions(scope, context);
+            let nixrt = match nixjs_rt_src {
+                Some(nixjs_rt_src) => load_nixjs_rt(scope, nixjs_rt_src)?,
+                None => get_nixjs_rt(scope)?,
+            };
+            register_nixjs_rt_module(scope, nixrt)?;
+            (
+                v8::Global::new(scope, context),
+                v8::Global::new(scope, nixrt),
+            )
+        };
+        Ok(Evaluator {
+            context,
+            nixrt,
+            isolate,
+            system: default_system(),
+        })
+    }
+
+    /// Caches transpiled and compiled Nix code in the given [`EvalCache`], or
+    /// disables caching if there is none. Caching is disabled by default.
+    pub fn set_eval_cache(&mut self, eval_cache: Option<EvalCache>) {
+        match eval_cache {
+            Some(eval_cache) => {
+                self.isolate.set_slot(eval_cache);
+            }
+            None => {
+                self.isolate.remove_slot::<EvalCache>();
+            }
+        }
+    }
+
+    /// Makes the evaluator import and read files from the given filesystem
+    /// rather than from the filesystem of the operating system.
+    pub fn set_fs(&mut self, fs: impl NixFs + 'static) {
+        set_fs(&mut self.isolate, Rc::new(fs));
+    }
+
+    /// Restricts the paths that the evaluated code can access. There are no
+    /// restrictions by default.
+    pub fn set_restrictions(&mut self, restrictions: EvalRestrictions) {
+        set_restrictions(&mut self.isolate, restrictions);
+    }
+
+    /// Sets the system type returned by `builtins.currentSystem`, e.g. to
+    /// evaluate for another platform. Defaults to [`default_system`].
+    pub fn set_system(&mut self, system: impl Into<String>) {
+        self.system = system.into();
+    }
+
+    pub fn evaluate(&mut self, nix_expr: &str, workdir: &Path) -> EvalResult {
+        self.evaluate_module(nix_expr, workdir, None)
+    }
+
+    /// Returns the Nix source of a file that the last evaluation compiled, or
+    /// reads it from the evaluator's filesystem otherwise, e.g. if the file
+    /// has a syntax error.
+    pub fn nix_source(&self, file: &str) -> Option<String> {
+        get_nix_module_source(&self.isolate, file)
+            .map(str::to_owned)
+            .or_else(|| get_fs(&self.isolate).read_to_string(Path::new(file)).ok())
+    }
+
+    /// Evaluates the given expression and selects the value at the given
+    /// attribute path. Only the selected value is forced. Like `nix eval`, if
+    /// the expression is a function with an attrset pattern, it's called with
+    /// an empty attrset first.
+    pub fn evaluate_attr_path(
+        &mut self,
+        nix_expr: &str,
+        workdir: &Path,
+        attr_path: &[String],
+    ) -> EvalResult {
+        self.evaluate_module(nix_expr, workdir, Some(attr_path))
+    }
+
+    /// Evaluates the given expression. With an attribute path, the value is
+    /// auto-called and the attribute path is selected.
+    fn evaluate_module(
+        &mut self,
+        nix_expr: &str,
+        workdir: &Path,
+        attr_path: Option<&[String]>,
+    ) -> EvalResult {
+        self.isolate.set_slot(EvalWorkdir(workdir.to_path_buf()));
+        let scope = &mut v8::HandleScope::with_context(&mut self.isolate, &self.context);
+        forget_nix_modules(scope);
+        set_builtin_constants(scope, &self.system);
+        let nixjs_rt_obj = v8::Local::new(scope, &self.nixrt);
+        let root_nix_fn = nix_expr_to_js_function(scope, nix_expr)?;
+        nix_value_from_module(scope, root_nix_fn, nixjs_rt_obj, workdir, attr_path)
+    }
+}
+
+/// The working directory of the current evaluation, against which the host
+/// functions resolve relative paths.
+struct EvalWorkdir(PathBuf);
+
+/// Inserts the functions that the Nix runtime calls into the host. Native
+/// functions can't be stored in the startup snapshot, so they are inserted
+/// into every context.
+fn insert_host_functions(scope: &mut HandleScope, context: v8::Local<v8::Context>) {
     let global = context.global(scope);
 
     // Insert all globals, as defined by globals.d.ts
@@ -25,6 +214,35 @@ pub fn evaluate(nix_expr: &str, workdir: &Path) -> EvalResult {
             "importNixModule",
             v8::Function::new(scope, import_nix_module).unwrap().into(),
         ),
+        (
+            "readFile",
+            v8::Function::new(scope, read_file).unwrap().into(),
+        ),
+        (
+            "readDir",
+            v8::Function::new(scope, read_dir).unwrap().into(),
+        ),
+        (
+            "pathExists",
+            v8::Function::new(scope, path_exists).unwrap().into(),
+        ),
+        (
+            "readFileType",
+            v8::Function::new(scope, read_file_type).unwrap().into(),
+        ),
+        (
+            "hashString",
+            v8::Function::new(scope, hash_string).unwrap().into(),
+        ),
+        (
+            "hashFile",
+            v8::Function::new(scope, hash_file).unwrap().into(),
+        ),
+        (
+            "convertHash",
+            v8::Function::new(scope, convert_hash).unwrap().into(),
+        ),
+        ("getEnv", v8::Function::new(scope, get_env).unwrap().into()),
         (
             "debugLog",
             v8::Function::new(scope, debug_log).unwrap().into(),
@@ -35,64 +253,109 @@ pub fn evaluate(nix_expr: &str, workdir: &Path) -> EvalResult {
         let global_var_name = v8::String::new(scope, name).unwrap();
         global.set(scope, global_var_name.into(), *value).unwrap();
     }
+}
 
-    // Execute the Nix runtime JS module, get its exports
-    let nixjs_rt_str = include_str!("../../nixjs-rt/dist/lib.mjs");
-    let nixjs_rt_obj = exec_module(nixjs_rt_str, scope)?;
-
-    // Set them to a global variable
-    let nixrt_attr = v8::String::new(scope, "n").unwrap();
-    global
-        .set(scope, nixrt_attr.into(), nixjs_rt_obj.into())
-        .unwrap();
-
-    let root_nix_fn = nix_expr_to_js_function(scope, nix_expr)?;
+/// Sets the global `builtinConstants`, which the Nix runtime adds to the
+/// builtins set. The current time is hidden in pure evaluation mode.
+fn set_builtin_constants(scope: &mut HandleScope, system: &str) {
+    let constants = v8::Object::new(scope);
+    let set = |scope: &mut HandleScope, name: &str, value: v8::Local<v8::Value>| {
+        let key = v8::String::new(scope, name).unwrap();
+        constants.set(scope, key.into(), value);
+    };
+    let current_system = v8::String::new(scope, system).unwrap();
+    set(scope, "currentSystem", current_system.into());
+    let nix_version = v8::String::new(scope, NIX_VERSION).unwrap();
+    set(scope, "nixVersion", nix_version.into());
+    let lang_version = v8::Integer::new_from_unsigned(scope, LANG_VERSION);
+    set(scope, "langVersion", lang_version.into());
+    if !get_restrictions(scope).is_time_hidden() {
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let current_time = v8::Number::new(scope, current_time as f64);
+        set(scope, "currentTime", current_time.into());
+    }
 
-    nix_value_from_module(scope, root_nix_fn, nixjs_rt_obj, workdir)
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "builtinConstants").unwrap();
+    global.set(scope, key.into(), constants.into());
 }
 
 fn nix_expr_to_js_function<'s>(
     scope: &mut HandleScope<'s>,
     nix_expr: &str,
 ) -> Result<v8::Local<'s, v8::Function>, NixError> {
-    let source_str = emit_module(nix_expr)?;
-    let module_source_v8 = to_v8_source(scope, &source_str, "<eval string>");
-    let module = v8::script_compiler::compile_module(scope, module_source_v8)
-        .ok_or("Failed to compile the module.")?;
-
-    if module
-        .instantiate_module(scope, resolve_module_callback)
-        .is_none()
+    let module = compile_nix_module(scope, nix_expr, ROOT_MODULE_NAME)?;
+    module_default_export(scope, module)
+}
+
+/// Instantiates and evaluates the module, unless that's already been done,
+/// and returns the function that the transpiled Nix module exports.
+fn module_default_export<'s>(
+    scope: &mut HandleScope<'s>,
+    module: v8::Local<v8::Module>,
+) -> Result<v8::Local<'s, v8::Function>, NixError> {
+    try_module_default_export(scope, module).map_err(|exception| match get_nixjs_rt(scope) {
+        Ok(nixjs_rt_obj) => js_error_to_rust(scope, nixjs_rt_obj, exception),
+        Err(err) => err.into(),
+    })
+}
+
+/// Like [`module_default_export`], but returns the JavaScript exception if
+/// the module couldn't be instantiated or evaluated.
+fn try_module_default_export<'s>(
+    scope: &mut HandleScope<'s>,
+    module: v8::Local<v8::Module>,
+) -> Result<v8::Local<'s, v8::Function>, v8::Local<'s, v8::Value>> {
+    let try_scope = &mut v8::TryCatch::new(scope);
+    if module.get_status() == ModuleStatus::Uninstantiated
+        && module
+            .instantiate_module(try_scope, resolve_module_callback)
+            .is_none()
     {
-        todo!("Instantiation failure.")
+        return Err(caught_exception(
+            try_scope,
+            "Failed to instantiate the module.",
+        ));
     }
 
-    if module.evaluate(scope).is_none() {
-        todo!("evaluation failed")
-    };
+    if module.get_status() == ModuleStatus::Instantiated && module.evaluate(try_scope).is_none() {
+        return Err(caught_exception(
+            try_scope,
+            "Failed to evaluate the module.",
+        ));
+    }
 
     if module.get_status() == ModuleStatus::Errored {
-        let exception = module.get_exception();
-        let string = exception.to_rust_string_lossy(scope);
-
-        todo!("evaluation failed:\n{}", string);
+        return Err(v8::Local::new(try_scope, module.get_exception()));
     }
 
-    let namespace_obj = module
+    let default_export = module
         .get_module_namespace()
-        .to_object(scope)
-        .ok_or("Failed to get the module namespace.")?;
-
-    let Some(nix_value) = try_get_js_object_key(scope, &namespace_obj.into(), "default")? else {
-        todo!(
-            "Could not find the nix value: {:?}",
-            namespace_obj.to_rust_string_lossy(scope)
-        )
-    };
-    let nix_value: v8::Local<v8::Function> =
-        nix_value.try_into().expect("Nix value must be a function.");
+        .to_object(try_scope)
+        .and_then(|namespace_obj| {
+            let default_key = v8::String::new(try_scope, "default").unwrap();
+            namespace_obj.get(try_scope, default_key.into())
+        })
+        .and_then(|default_export| v8::Local::<v8::Function>::try_from(default_export).ok());
+    default_export.ok_or_else(|| {
+        let message =
+            v8::String::new(try_scope, "The module doesn't export a default function.").unwrap();
+        v8::Exception::error(try_scope, message)
+    })
+}
 
-    Ok(nix_value)
+/// Returns the exception caught by the scope or, if there isn't one, e.g.
+/// because the execution was terminated, an error with the given message.
+fn caught_exception<'s>(
+    try_scope: &mut v8::TryCatch<HandleScope<'s>>,
+    message: &str,
+) -> v8::Local<'s, v8::Value> {
+    try_scope.exception().unwrap_or_else(|| {
+        let message = v8::String::new(try_scope, message).unwrap();
+        v8::Exception::error(try_scope, message)
+    })
 }
 
 fn import_nix_module<'s>(
@@ -101,71 +364,365 @@ fn import_nix_module<'s>(
     mut ret: v8::ReturnValue,
 ) {
     let module_path = args.get(0).to_rust_string_lossy(scope);
-    let module_source_str = std::fs::read_to_string(module_path).unwrap();
+    let Some(resolved_path) = resolve_allowed_path(scope, &module_path) else {
+        return;
+    };
+
+    match import_nix_file(scope, &resolved_path) {
+        Ok(Some(nix_value)) => ret.set(nix_value),
+        // The module threw an exception, which propagates to the caller.
+        Ok(None) => {}
+        Err(NixError {
+            kind:
+                NixErrorKind::ParseError {
+                    file,
+                    line,
+                    column,
+                    message,
+                },
+            ..
+        }) => throw_parse_error(scope, &file, line, column, &message),
+        Err(err) => throw_import_failed_error(scope, &module_path, &err.to_string()),
+    }
+}
+
+/// Throws a `NixParseError` from the Nix runtime for a syntax error in an
+/// imported file.
+fn throw_parse_error(
+    scope: &mut HandleScope,
+    file: &str,
+    line: usize,
+    column: usize,
+    message: &str,
+) {
+    let args = [
+        v8::String::new(scope, file).unwrap().into(),
+        v8::Number::new(scope, line as f64).into(),
+        v8::Number::new(scope, column as f64).into(),
+        v8::String::new(scope, message).unwrap().into(),
+    ];
+    throw_nixrt_error_with_args(scope, "parseError", &args);
+}
 
-    let nix_fn = nix_expr_to_js_function(scope, &module_source_str);
+/// Throws a `NixImportFailedError` from the Nix runtime, so that the failure
+/// can be caught like any other Nix error.
+fn throw_import_failed_error(scope: &mut HandleScope, module_path: &str, reason: &str) {
+    throw_nixrt_error(scope, "importFailedError", &[module_path, reason]);
+}
 
-    let nix_fn = match nix_fn {
-        Ok(nix_fn) => nix_fn,
-        Err(err) => {
-            let err_str = v8::String::new(scope, &err.to_string()).unwrap();
-            let err_obj = v8::Exception::error(scope, err_str);
-            ret.set(err_obj);
-            return;
+/// Throws a `NixIoError` from the Nix runtime.
+fn throw_io_error(scope: &mut HandleScope, path: &str, err: &std::io::Error) {
+    throw_nixrt_error(scope, "ioError", &[path, &err.to_string()]);
+}
+
+/// Throws the error created by calling the given error function of the Nix
+/// runtime with the given string arguments.
+fn throw_nixrt_error(scope: &mut HandleScope, error_fn_name: &str, args: &[&str]) {
+    let js_args: Vec<v8::Local<v8::Value>> = args
+        .iter()
+        .map(|arg| v8::String::new(scope, arg).unwrap().into())
+        .collect();
+    throw_nixrt_error_with_args(scope, error_fn_name, &js_args);
+}
+
+/// Like [`throw_nixrt_error`], but with arguments of any type.
+fn throw_nixrt_error_with_args(
+    scope: &mut HandleScope,
+    error_fn_name: &str,
+    js_args: &[v8::Local<v8::Value>],
+) {
+    let error = get_nixjs_rt(scope).and_then(|nixjs_rt_obj| {
+        let error_fn: v8::Local<v8::Function> =
+            try_get_js_object_key(scope, &nixjs_rt_obj.into(), error_fn_name)?
+                .ok_or_else(|| {
+                    format!("Could not find the function `{error_fn_name}` in `nixrt`.")
+                })?
+                .try_into()
+                .map_err(|_| format!("`n.{error_fn_name}` is not a function."))?;
+        let undefined = v8::undefined(scope).into();
+        error_fn
+            .call(scope, undefined, js_args)
+            .ok_or_else(|| format!("Failed to create the error with `n.{error_fn_name}`."))
+    });
+    let exception = error.unwrap_or_else(|err| {
+        let message = v8::String::new(scope, &err).unwrap();
+        v8::Exception::error(scope, message)
+    });
+    scope.throw_exception(exception);
+}
+
+/// Returns the value of the Nix file at the given path. Every file is
+/// transpiled and evaluated at most once per evaluation, no matter through
+/// which path it is imported. Returns `None` if the evaluation threw an
+/// exception, which is then pending in the scope.
+fn import_nix_file<'s>(
+    scope: &mut HandleScope<'s>,
+    module_path: &Path,
+) -> Result<Option<v8::Local<'s, v8::Value>>, NixError> {
+    let canonical_path = get_fs(scope)
+        .canonicalize(module_path)
+        .map_err(|err| err.to_string())?;
+
+    let imported_file = match get_imported_file(scope, &canonical_path) {
+        Some(imported_file) => imported_file,
+        None => {
+            let specifier = canonical_path.to_str().ok_or_else(|| {
+                format!("Failed to convert the path {canonical_path:?} to a string.")
+            })?;
+            let module = resolve_module(scope, specifier)?;
+            let nix_fn = match try_module_default_export(scope, module) {
+                Ok(nix_fn) => nix_fn,
+                Err(exception) => {
+                    scope.throw_exception(exception);
+                    return Ok(None);
+                }
+            };
+            ImportedFile {
+                nix_fn: v8::Global::new(scope, nix_fn),
+                value: None,
+            }
         }
     };
+    if let Some(value) = &imported_file.value {
+        return Ok(Some(v8::Local::new(scope, value)));
+    }
+
+    let nixjs_rt_obj = get_nixjs_rt(scope)?;
+    let module_dir = canonical_path
+        .parent()
+        .ok_or_else(|| format!("Failed to determine the directory of path {canonical_path:?}."))?;
+    let eval_ctx = create_eval_ctx(scope, &nixjs_rt_obj.into(), module_dir)?;
+    let nix_fn = v8::Local::new(scope, &imported_file.nix_fn);
+    let value = nix_fn.call(scope, nixjs_rt_obj.into(), &[eval_ctx.into()]);
+
+    let imported_file = ImportedFile {
+        value: value.map(|value| v8::Global::new(scope, value)),
+        ..imported_file
+    };
+    set_imported_file(scope, canonical_path, imported_file);
+    Ok(value)
+}
 
-    ret.set(nix_fn.into());
+/// Resolves the path against the working directory of the evaluation and
+/// checks that the evaluation may access it. Otherwise, throws a
+/// `NixAccessForbiddenError` and returns `None`.
+fn resolve_allowed_path(scope: &mut HandleScope, path: &str) -> Option<PathBuf> {
+    let resolved_path = match scope.get_slot::<EvalWorkdir>() {
+        Some(EvalWorkdir(workdir)) => workdir.join(path),
+        None => PathBuf::from(path),
+    };
+    let restrictions = get_restrictions(scope);
+    if restrictions.is_path_allowed(get_fs(scope).as_ref(), &resolved_path) {
+        Some(resolved_path)
+    } else {
+        throw_nixrt_error(
+            scope,
+            "accessForbiddenError",
+            &[
+                &resolved_path.to_string_lossy(),
+                restrictions.mode.description(),
+            ],
+        );
+        None
+    }
 }
 
-fn debug_log<'s>(
+fn read_file<'s>(
     scope: &mut HandleScope<'s>,
     args: v8::FunctionCallbackArguments<'s>,
-    _ret: v8::ReturnValue,
+    mut ret: v8::ReturnValue,
 ) {
-    // Log the first argument
-    let log_str = args.get(0).to_rust_string_lossy(scope);
-    eprintln!("Log from JS: {log_str}");
+    let path = args.get(0).to_rust_string_lossy(scope);
+    let Some(resolved_path) = resolve_allowed_path(scope, &path) else {
+        return;
+    };
+    match get_fs(scope).read(&resolved_path) {
+        Ok(contents) => match v8::String::new(scope, &String::from_utf8_lossy(&contents)) {
+            Some(contents) => ret.set(contents.into()),
+            None => {
+                let err = std::io::Error::other("The file is too large.");
+                throw_io_error(scope, &path, &err);
+            }
+        },
+        Err(err) => throw_io_error(scope, &path, &err),
+    }
 }
 
-fn exec_module<'a>(
-    code: &str,
-    scope: &mut v8::HandleScope<'a>,
-) -> Result<Local<'a, Object>, NixError> {
-    let source = to_v8_source(scope, code, "<eval string>");
-    let module = v8::script_compiler::compile_module(scope, source)
-        .ok_or("Failed to compile the module.")?;
+fn read_dir<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let path = args.get(0).to_rust_string_lossy(scope);
+    let Some(resolved_path) = resolve_allowed_path(scope, &path) else {
+        return;
+    };
+    let entries = match get_fs(scope).read_dir(&resolved_path) {
+        Ok(entries) => entries,
+        Err(err) => return throw_io_error(scope, &path, &err),
+    };
 
-    if module
-        .instantiate_module(scope, resolve_module_callback)
-        .is_none()
-    {
-        return Err("Instantiation failure.".to_owned().into());
+    // The entries are returned as `[name, type]` pairs rather than as an
+    // object, so that names like `__proto__` are kept as they are.
+    let js_entries: Vec<v8::Local<v8::Value>> = entries
+        .iter()
+        .map(|(name, file_type)| {
+            let js_name = v8::String::new(scope, name).unwrap();
+            let js_file_type = v8::String::new(scope, file_type.name()).unwrap();
+            v8::Array::new_with_elements(scope, &[js_name.into(), js_file_type.into()]).into()
+        })
+        .collect();
+    ret.set(v8::Array::new_with_elements(scope, &js_entries).into());
+}
+
+fn path_exists<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let path = args.get(0).to_rust_string_lossy(scope);
+    let Some(resolved_path) = resolve_allowed_path(scope, &path) else {
+        return;
+    };
+    let exists = get_fs(scope).exists(&resolved_path);
+    ret.set(v8::Boolean::new(scope, exists).into());
+}
+
+fn read_file_type<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let path = args.get(0).to_rust_string_lossy(scope);
+    let Some(resolved_path) = resolve_allowed_path(scope, &path) else {
+        return;
+    };
+    match get_fs(scope).file_type(&resolved_path) {
+        Ok(file_type) => {
+            let file_type = v8::String::new(scope, file_type.name()).unwrap();
+            ret.set(file_type.into());
+        }
+        Err(err) => throw_io_error(scope, &path, &err),
+    }
+}
+
+fn hash_string<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let algo = args.get(0).to_rust_string_lossy(scope);
+    let data = args.get(1).to_rust_string_lossy(scope);
+    let format = args.get(2).to_rust_string_lossy(scope);
+    let hash = HashAlgo::parse(&algo).and_then(|algo| {
+        let format = HashFormat::parse(&format)?;
+        Ok(Hash::of(algo, data.as_bytes()).to_string(format))
+    });
+    return_hash(scope, hash, &mut ret);
+}
+
+fn hash_file<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let algo = args.get(0).to_rust_string_lossy(scope);
+    let path = args.get(1).to_rust_string_lossy(scope);
+    let format = args.get(2).to_rust_string_lossy(scope);
+    let Some(resolved_path) = resolve_allowed_path(scope, &path) else {
+        return;
+    };
+    match get_fs(scope).read(&resolved_path) {
+        Ok(contents) => {
+            let hash = HashAlgo::parse(&algo).and_then(|algo| {
+                let format = HashFormat::parse(&format)?;
+                Ok(Hash::of(algo, &contents).to_string(format))
+            });
+            return_hash(scope, hash, &mut ret);
+        }
+        Err(err) => throw_io_error(scope, &path, &err),
     }
+}
+
+fn convert_hash<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let hash = args.get(0).to_rust_string_lossy(scope);
+    let algo = args.get(1);
+    let algo = (!algo.is_undefined()).then(|| algo.to_rust_string_lossy(scope));
+    let format = args.get(2).to_rust_string_lossy(scope);
+    let converted = algo
+        .as_deref()
+        .map(HashAlgo::parse)
+        .transpose()
+        .and_then(|algo| {
+            let format = HashFormat::parse(&format)?;
+            Ok(Hash::parse(&hash, algo)?.to_string(format))
+        });
+    return_hash(scope, converted, &mut ret);
+}
 
-    if module.evaluate(scope).is_none() {
-        return Err("Evaluation failure.".to_owned().into());
+/// Returns the hash to the Nix runtime, or throws a `NixOtherError` if it
+/// couldn't be computed.
+fn return_hash(scope: &mut HandleScope, hash: Result<String, String>, ret: &mut v8::ReturnValue) {
+    match hash {
+        Ok(hash) => ret.set(v8::String::new(scope, &hash).unwrap().into()),
+        Err(err) => throw_nixrt_error(scope, "otherError", &[&err, "builtins-invalid-hash"]),
     }
+}
 
-    let obj = module
-        .get_module_namespace()
-        .to_object(scope)
-        .ok_or("Failed to get the module namespace.")?;
+fn get_env<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    mut ret: v8::ReturnValue,
+) {
+    let name = args.get(0).to_rust_string_lossy(scope);
+    let value = if get_restrictions(scope).is_environment_hidden() {
+        String::new()
+    } else {
+        std::env::var_os(name)
+            .map(|value| value.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    ret.set(v8::String::new(scope, &value).unwrap().into());
+}
 
-    Ok(obj)
+fn debug_log<'s>(
+    scope: &mut HandleScope<'s>,
+    args: v8::FunctionCallbackArguments<'s>,
+    _ret: v8::ReturnValue,
+) {
+    // Log the first argument
+    let log_str = args.get(0).to_rust_string_lossy(scope);
+    eprintln!("Log from JS: {log_str}");
 }
 
 fn nix_value_from_module(
-    scope: &mut v8::ContextScope<v8::HandleScope>,
+    scope: &mut v8::HandleScope,
     nix_module_fn: v8::Local<v8::Function>,
     nixjs_rt_obj: v8::Local<v8::Object>,
     workdir: &Path,
+    attr_path: Option<&[String]>,
 ) -> EvalResult {
     let nixrt: v8::Local<v8::Value> = nixjs_rt_obj.into();
 
     let eval_ctx = create_eval_ctx(scope, &nixrt, workdir)?;
 
-    let nix_value = call_js_function(scope, &nix_module_fn, nixjs_rt_obj, &[eval_ctx.into()])?;
+    let mut nix_value = call_js_function(scope, &nix_module_fn, nixjs_rt_obj, &[eval_ctx.into()])?;
+
+    if let Some(attr_path) = attr_path {
+        let auto_call_fn: v8::Local<v8::Function> =
+            try_get_js_object_key(scope, &nixrt, "autoCallFunction")?
+                .expect("Could not find the function `autoCallFunction` in `nixrt`.")
+                .try_into()
+                .expect("`n.autoCallFunction` is not a function.");
+        nix_value = call_js_function(scope, &auto_call_fn, nixjs_rt_obj, &[nix_value])?;
+        if !attr_path.is_empty() {
+            nix_value = select_attr_path(scope, &nixrt, nixjs_rt_obj, nix_value, attr_path)?;
+        }
+    }
 
     let to_strict_fn: v8::Local<v8::Function> =
         try_get_js_object_key(scope, &nixrt, "recursiveStrict")?
@@ -177,6 +734,43 @@ fn nix_value_from_module(
     js_value_to_nix(scope, &nixjs_rt_obj, &strict_nix_value)
 }
 
+fn select_attr_path<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    nixrt: &v8::Local<v8::Value>,
+    nixjs_rt_obj: v8::Local<v8::Object>,
+    nix_value: v8::Local<'s, v8::Value>,
+    attr_path: &[String],
+) -> Result<v8::Local<'s, v8::Value>, NixError> {
+    let nix_string_constructor: v8::Local<v8::Function> =
+        get_nixrt_type(scope, nixrt, "NixString")?
+            .try_into()
+            .expect("Could not get the constructor of `NixString`.");
+    let js_attr_path: Vec<v8::Local<v8::Value>> = attr_path
+        .iter()
+        .map(|attr_name| {
+            let js_attr_name = v8::String::new(scope, attr_name).unwrap();
+            nix_string_constructor
+                .new_instance(scope, &[js_attr_name.into()])
+                .expect("Could not construct a `NixString`.")
+                .into()
+        })
+        .collect();
+    let js_attr_path = v8::Array::new_with_elements(scope, &js_attr_path);
+
+    let select_fn: v8::Local<v8::Function> = try_get_js_object_key(scope, &nix_value, "select")?
+        .expect("Could not find the method `select` on the Nix value.")
+        .try_into()
+        .expect("`select` is not a function.");
+    let undefined = v8::undefined(scope).into();
+    call_js_instance_mehod(
+        scope,
+        &select_fn,
+        nix_value,
+        nixjs_rt_obj,
+        &[js_attr_path.into(), undefined],
+    )
+}
+
 fn create_eval_ctx<'s>(
     scope: &mut v8::HandleScope<'s>,
     nixrt: &v8::Local<v8::Value>,
@@ -187,8 +781,8 @@ fn create_eval_ctx<'s>(
         .try_into()
         .expect("Could not get the constructor of the evaluation context class.");
 
-    let real_path = script_path
-        .canonicalize()
+    let real_path = get_fs(scope)
+        .canonicalize(script_path)
         .map_err(|err| format!("Failed to resolve the script path. Error: {err}."))?;
     let script_dir = real_path
         .parent()
@@ -203,50 +797,3 @@ fn create_eval_ctx<'s>(
         .new_instance(scope, &[js_script_dir_path.into()])
         .expect("Could not construct the global evaluation context."))
 }
-
-fn new_script_origin<'s>(
-    scope: &mut v8::HandleScope<'s>,
-    resource_name: &str,
-    source_map_url: &str,
-) -> v8::ScriptOrigin<'s> {
-    let resource_name_v8_str = v8::String::new(scope, resource_name).unwrap();
-    let resource_line_offset = 0;
-    let resource_column_offset = 0;
-    let resource_is_shared_cross_origin = true;
-    let script_id = 123;
-    let source_map_url = v8::String::new(scope, source_map_url).unwrap();
-    let resource_is_opaque = false;
-    let is_wasm = false;
-    let is_module = true;
-    v8::ScriptOrigin::new(
-        scope,
-        resource_name_v8_str.into(),
-        resource_line_offset,
-        resource_column_offset,
-        resource_is_shared_cross_origin,
-        script_id,
-        source_map_url.into(),
-        resource_is_opaque,
-        is_wasm,
-        is_module,
-    )
-}
-
-fn to_v8_source(
-    scope: &mut v8::HandleScope,
-    js_code: &str,
-    source_path: &str,
-) -> v8::script_compiler::Source {
-    let code = v8::String::new(scope, js_code).unwrap();
-    let origin = new_script_origin(scope, source_path, &format!("file://{source_path}.map"));
-    v8::script_compiler::Source::new(code, Some(&origin))
-}
-
-fn resolve_module_callback<'a>(
-    _: v8::Local<'a, v8::Context>,
-    _: v8::Local<'a, v8::String>,
-    _: v8::Local<'a, v8::FixedArray>,
-    _: v8::Local<'a, v8::Module>,
-) -> Option<v8::Local<'a, v8::Module>> {
-    panic!("Module resolution not supported.")
-}
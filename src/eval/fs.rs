@@ -0,0 +1,237 @@
+//! The filesystem from which evaluations import Nix files and read files.
+//!
+//! Evaluators use the [`OsFs`] by default. An [`InMemoryFs`] lets them
+//! evaluate Nix code that doesn't exist on disk, e.g. in tests or when the
+//! files come from an archive.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use deno_core::v8;
+
+/// File access of the host functions. All paths are absolute.
+pub trait NixFs {
+    /// Returns the absolute path without `.` and `..` components and with all
+    /// symlinks resolved. Fails if the path doesn't exist.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Returns the names and types of the entries of the directory, sorted by
+    /// name. Symlinks are not followed.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, FileType)>>;
+
+    /// Returns the type of the file. Symlinks are not followed.
+    fn file_type(&self, path: &Path) -> io::Result<FileType>;
+
+    /// Whether the path exists. Symlinks are followed.
+    fn exists(&self, path: &Path) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileType {
+    Regular,
+    Directory,
+    Symlink,
+    Unknown,
+}
+
+impl FileType {
+    /// The name of the file type as returned by `builtins.readFileType`.
+    pub fn name(self) -> &'static str {
+        match self {
+            FileType::Regular => "regular",
+            FileType::Directory => "directory",
+            FileType::Symlink => "symlink",
+            FileType::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<std::fs::FileType> for FileType {
+    fn from(file_type: std::fs::FileType) -> Self {
+        if file_type.is_symlink() {
+            FileType::Symlink
+        } else if file_type.is_dir() {
+            FileType::Directory
+        } else if file_type.is_file() {
+            FileType::Regular
+        } else {
+            FileType::Unknown
+        }
+    }
+}
+
+/// The filesystem of the operating system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFs;
+
+impl NixFs for OsFs {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, FileType)>> {
+        let mut entries = std::fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                Ok((name, entry.file_type()?.into()))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn file_type(&self, path: &Path) -> io::Result<FileType> {
+        Ok(std::fs::symlink_metadata(path)?.file_type().into())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+/// A filesystem that holds regular files in memory. Directories exist
+/// implicitly as the parents of files.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryFs {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl InMemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the file at the given absolute path, or replaces it.
+    pub fn add_file(&mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        self.files
+            .insert(normalize_path(path.as_ref()), contents.into());
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .keys()
+            .any(|file_path| file_path != path && file_path.starts_with(path))
+    }
+}
+
+impl NixFs for InMemoryFs {
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize_path(path);
+        if self.exists(&path) {
+            Ok(path)
+        } else {
+            Err(not_found())
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let path = normalize_path(path);
+        match self.files.get(&path) {
+            Some(contents) => Ok(contents.clone()),
+            None if self.is_dir(&path) => Err(io::Error::other("Is a directory")),
+            None => Err(not_found()),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<(String, FileType)>> {
+        let path = normalize_path(path);
+        if !self.is_dir(&path) {
+            return Err(if self.files.contains_key(&path) {
+                io::Error::other("Not a directory")
+            } else {
+                not_found()
+            });
+        }
+        let mut entries: Vec<(String, FileType)> = self
+            .files
+            .keys()
+            .filter_map(|file_path| {
+                let mut components = file_path.strip_prefix(&path).ok()?.components();
+                let name = components
+                    .next()?
+                    .as_os_str()
+                    .to_string_lossy()
+                    .into_owned();
+                let file_type = match components.next() {
+                    Some(_) => FileType::Directory,
+                    None => FileType::Regular,
+                };
+                Some((name, file_type))
+            })
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn file_type(&self, path: &Path) -> io::Result<FileType> {
+        let path = normalize_path(path);
+        if self.files.contains_key(&path) {
+            Ok(FileType::Regular)
+        } else if self.is_dir(&path) {
+            Ok(FileType::Directory)
+        } else {
+            Err(not_found())
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalize_path(path);
+        self.files.contains_key(&path) || self.is_dir(&path)
+    }
+}
+
+/// Removes `.` and `..` components from the path without accessing the
+/// filesystem.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn not_found() -> io::Error {
+    io::Error::from(io::ErrorKind::NotFound)
+}
+
+/// The filesystem of an isolate.
+struct IsolateFs(Rc<dyn NixFs>);
+
+/// Makes the host functions of the isolate access files through the given
+/// filesystem.
+pub fn set_fs(isolate: &mut v8::Isolate, fs: Rc<dyn NixFs>) {
+    isolate.set_slot(IsolateFs(fs));
+}
+
+/// Returns the filesystem of the isolate, which is the [`OsFs`] unless
+/// another one was set.
+pub fn get_fs(isolate: &v8::Isolate) -> Rc<dyn NixFs> {
+    match isolate.get_slot::<IsolateFs>() {
+        Some(IsolateFs(fs)) => fs.clone(),
+        None => Rc::new(OsFs),
+    }
+}
@@ -0,0 +1,250 @@
+//! Cryptographic hashes in the formats that Nix uses, for `builtins.hashString`,
+//! `builtins.hashFile` and `builtins.convertHash`.
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgo {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl HashAlgo {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "md5" => Ok(HashAlgo::Md5),
+            "sha1" => Ok(HashAlgo::Sha1),
+            "sha256" => Ok(HashAlgo::Sha256),
+            "sha512" => Ok(HashAlgo::Sha512),
+            _ => Err(format!("unknown hash algorithm '{name}'")),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Sha1 => "sha1",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha512 => "sha512",
+        }
+    }
+
+    /// The size of the hash in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            HashAlgo::Md5 => 16,
+            HashAlgo::Sha1 => 20,
+            HashAlgo::Sha256 => 32,
+            HashAlgo::Sha512 => 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFormat {
+    Base16,
+    /// Nix's own base32 encoding, which differs from RFC 4648.
+    Nix32,
+    Base64,
+    /// A [Subresource Integrity](https://www.w3.org/TR/SRI/) hash, e.g.
+    /// `sha256-LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=`.
+    Sri,
+}
+
+impl HashFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "base16" => Ok(HashFormat::Base16),
+            // Nix accepts `base32` as a deprecated name of `nix32`.
+            "nix32" | "base32" => Ok(HashFormat::Nix32),
+            "base64" => Ok(HashFormat::Base64),
+            "sri" => Ok(HashFormat::Sri),
+            _ => Err(format!("unknown hash format '{name}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hash {
+    pub algo: HashAlgo,
+    pub bytes: Vec<u8>,
+}
+
+impl Hash {
+    /// Hashes the data with the given algorithm.
+    pub fn of(algo: HashAlgo, data: &[u8]) -> Self {
+        let bytes = match algo {
+            HashAlgo::Md5 => Md5::digest(data).to_vec(),
+            HashAlgo::Sha1 => Sha1::digest(data).to_vec(),
+            HashAlgo::Sha256 => Sha256::digest(data).to_vec(),
+            HashAlgo::Sha512 => Sha512::digest(data).to_vec(),
+        };
+        Hash { algo, bytes }
+    }
+
+    /// Parses a hash in any of the formats, like `builtins.convertHash`. The
+    /// algorithm is taken from SRI hashes and from hashes prefixed with
+    /// `<algo>:`. Otherwise, it must be given. The encoding of hashes without
+    /// a prefix is determined by their length.
+    pub fn parse(hash: &str, algo: Option<HashAlgo>) -> Result<Self, String> {
+        let (prefix_algo, encoded, is_sri) = if let Some((name, rest)) = hash.split_once(':') {
+            (Some(HashAlgo::parse(name)?), rest, false)
+        } else if let Some((name, rest)) = hash.split_once('-') {
+            (Some(HashAlgo::parse(name)?), rest, true)
+        } else {
+            (None, hash, false)
+        };
+        let algo = match (prefix_algo, algo) {
+            (Some(prefix_algo), Some(algo)) if prefix_algo != algo => {
+                return Err(format!(
+                    "hash '{hash}' should have type '{}'",
+                    algo.name()
+                ))
+            }
+            (Some(algo), _) | (None, Some(algo)) => algo,
+            (None, None) => {
+                return Err(format!(
+                    "hash '{hash}' does not include a type, nor is the type otherwise known from context"
+                ))
+            }
+        };
+
+        let size = algo.size();
+        let bytes = if is_sri {
+            decode_base64(encoded)
+        } else if encoded.len() == size * 2 {
+            decode_base16(encoded)
+        } else if encoded.len() == nix32_len(size) {
+            decode_nix32(encoded, size)
+        } else if encoded.len() == base64_len(size) {
+            decode_base64(encoded)
+        } else {
+            None
+        };
+        match bytes {
+            Some(bytes) if bytes.len() == size => Ok(Hash { algo, bytes }),
+            _ => Err(format!(
+                "hash '{hash}' has wrong length for hash type '{}'",
+                algo.name()
+            )),
+        }
+    }
+
+    pub fn to_string(&self, format: HashFormat) -> String {
+        match format {
+            HashFormat::Base16 => encode_base16(&self.bytes),
+            HashFormat::Nix32 => encode_nix32(&self.bytes),
+            HashFormat::Base64 => encode_base64(&self.bytes),
+            HashFormat::Sri => format!("{}-{}", self.algo.name(), encode_base64(&self.bytes)),
+        }
+    }
+}
+
+const BASE16_CHARS: &[u8; 16] = b"0123456789abcdef";
+
+/// The alphabet of Nix's base32 encoding, which omits `e`, `o`, `u` and `t`.
+const NIX32_CHARS: &[u8; 32] = b"0123456789abcdfghijklmnpqrsvwxyz";
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn encode_base16(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .map(|digit| BASE16_CHARS[digit as usize] as char)
+        .collect()
+}
+
+fn decode_base16(encoded: &str) -> Option<Vec<u8>> {
+    let digit = |char: u8| (char as char).to_digit(16);
+    encoded
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| Some((digit(pair[0])? * 16 + digit(*pair.get(1)?)?) as u8))
+        .collect()
+}
+
+fn nix32_len(size: usize) -> usize {
+    (size * 8 - 1) / 5 + 1
+}
+
+/// Encodes the bytes like Nix, which reads them as a little-endian number and
+/// prints its 5-bit digits starting with the most significant one.
+fn encode_nix32(bytes: &[u8]) -> String {
+    (0..nix32_len(bytes.len()))
+        .rev()
+        .map(|n| {
+            let bit = n * 5;
+            let (i, j) = (bit / 8, bit % 8);
+            let low = bytes[i] >> j;
+            let high = bytes
+                .get(i + 1)
+                .map_or(0, |byte| byte.checked_shl(8 - j as u32).unwrap_or(0));
+            NIX32_CHARS[((low | high) & 0x1f) as usize] as char
+        })
+        .collect()
+}
+
+fn decode_nix32(encoded: &str, size: usize) -> Option<Vec<u8>> {
+    let mut bytes = vec![0u8; size];
+    for (n, char) in encoded.bytes().rev().enumerate() {
+        let digit = NIX32_CHARS.iter().position(|c| *c == char)? as u16;
+        let bit = n * 5;
+        let (i, j) = (bit / 8, bit % 8);
+        let shifted = digit << j;
+        bytes[i] |= shifted as u8;
+        let carry = (shifted >> 8) as u8;
+        match bytes.get_mut(i + 1) {
+            Some(byte) => *byte |= carry,
+            // The encoding has more bits than the hash, and they must be 0.
+            None if carry != 0 => return None,
+            None => {}
+        }
+    }
+    Some(bytes)
+}
+
+fn base64_len(size: usize) -> usize {
+    size.div_ceil(3) * 4
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(base64_len(bytes.len()));
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |triple, (idx, byte)| {
+            triple | (*byte as u32) << (16 - 8 * idx)
+        });
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                encoded.push(BASE64_CHARS[(triple >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    if encoded.len() % 4 != 0 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+    for quad in encoded.as_bytes().chunks(4) {
+        let padding = quad.iter().rev().take_while(|char| **char == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut triple = 0u32;
+        for (idx, char) in quad[..4 - padding].iter().enumerate() {
+            let digit = BASE64_CHARS.iter().position(|c| c == char)? as u32;
+            triple |= digit << (18 - 6 * idx);
+        }
+        bytes.extend_from_slice(&triple.to_be_bytes()[1..4 - padding]);
+    }
+    Some(bytes)
+}
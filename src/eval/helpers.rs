@@ -102,7 +102,6 @@ pub fn map_js_exception_value_to_rust<'s>(
     nixrt: v8::Local<v8::Object>,
     exception: Option<v8::Local<'s, v8::Value>>,
 ) -> NixError {
-    // TODO: Again, the stack trace needs to be source-mapped.
     if let Some(error) = exception {
         js_error_to_rust(scope, nixrt, error)
     } else {
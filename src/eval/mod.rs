@@ -1,5 +1,13 @@
 pub mod emit_js;
 pub mod error;
+pub mod eval_cache;
 pub mod execution;
+pub mod fs;
+pub mod hash;
 pub mod helpers;
+pub mod modules;
+pub mod nixjs_rt;
+pub mod print;
+pub mod restrictions;
+pub mod source_map;
 pub mod types;
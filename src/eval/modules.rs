@@ -0,0 +1,424 @@
+//! Resolves the ES modules imported by transpiled Nix code.
+//!
+//! Each isolate has a [`ModuleMap`] that holds the Nix runtime as the module
+//! `nixjs-rt` and the transpiled Nix files, keyed by their absolute paths.
+//! The values of imported files are memoized in an [`ImportCache`]. If the
+//! isolate has an [`EvalCache`], transpiled files are also cached on disk.
+//! The sources and source maps of compiled Nix modules are kept in
+//! [`NixModuleSources`] to rebuild Nix stack traces from V8 stack traces.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use deno_core::v8;
+
+use super::emit_js::{describe_expr_at, emit_module_with_source_map};
+use super::error::{NixError, NixStackFrame};
+use super::eval_cache::EvalCache;
+use super::fs::get_fs;
+use super::nixjs_rt::get_nixjs_rt;
+use super::source_map::{position_to_offset, SourceMap};
+
+/// The specifier through which transpiled Nix code imports the Nix runtime.
+pub const NIXJS_RT_MODULE: &str = "nixjs-rt";
+
+/// The name of the module compiled from the evaluated expression, which is
+/// how Nix refers to expressions that aren't read from files.
+pub const ROOT_MODULE_NAME: &str = "«string»";
+
+/// The modules known to an isolate, keyed by their specifiers.
+#[derive(Default)]
+struct ModuleMap {
+    modules: HashMap<String, v8::Global<v8::Module>>,
+}
+
+/// The Nix files imported during the current evaluation, keyed by their
+/// canonical paths.
+#[derive(Default)]
+struct ImportCache {
+    files: HashMap<PathBuf, ImportedFile>,
+}
+
+/// The Nix sources of the compiled Nix modules and the source maps of their
+/// transpiled JavaScript, keyed by module name.
+#[derive(Default)]
+struct NixModuleSources {
+    modules: HashMap<String, NixModuleSource>,
+}
+
+struct NixModuleSource {
+    nix_source: String,
+    source_map: SourceMap,
+}
+
+/// A Nix file that `builtins.import` has already compiled.
+#[derive(Clone)]
+pub struct ImportedFile {
+    /// The default export of the module, which takes an `EvalCtx`.
+    pub nix_fn: v8::Global<v8::Function>,
+    /// The value of the file, unless its evaluation threw an exception.
+    pub value: Option<v8::Global<v8::Value>>,
+}
+
+/// Registers the Nix runtime, which must already be loaded into the global
+/// variable `n`, as the module `nixjs-rt`.
+pub fn register_nixjs_rt_module(
+    scope: &mut v8::HandleScope,
+    nixjs_rt_obj: v8::Local<v8::Object>,
+) -> Result<(), NixError> {
+    let export_names_array = nixjs_rt_obj
+        .get_own_property_names(scope, Default::default())
+        .ok_or("Failed to get the exports of the Nix runtime.")?;
+    let mut export_names = Vec::with_capacity(export_names_array.length() as usize);
+    for idx in 0..export_names_array.length() {
+        let export_name: v8::Local<v8::String> = export_names_array
+            .get_index(scope, idx)
+            .ok_or("Unexpected index out-of-bounds.")?
+            .try_into()
+            .map_err(|err| format!("Export names must be strings. Error: {err:?}"))?;
+        export_names.push(export_name);
+    }
+
+    let module_name = v8::String::new(scope, NIXJS_RT_MODULE).unwrap();
+    let module = v8::Module::create_synthetic_module(
+        scope,
+        module_name,
+        &export_names,
+        nixjs_rt_module_evaluation_steps,
+    );
+    module
+        .instantiate_module(scope, resolve_module_callback)
+        .ok_or("Failed to instantiate the Nix runtime module.")?;
+    register_module(scope, NIXJS_RT_MODULE, module);
+    Ok(())
+}
+
+/// Forgets all transpiled Nix files and the values of imported files so that
+/// they are read again the next time they're imported. Only the Nix runtime
+/// module is kept.
+pub fn forget_nix_modules(scope: &mut v8::HandleScope) {
+    if let Some(module_map) = scope.get_slot_mut::<ModuleMap>() {
+        module_map
+            .modules
+            .retain(|specifier, _| specifier == NIXJS_RT_MODULE);
+    }
+    scope.remove_slot::<ImportCache>();
+    scope.remove_slot::<NixModuleSources>();
+}
+
+/// Returns the Nix source of a module that was compiled during the current
+/// evaluation.
+pub fn get_nix_module_source<'a>(isolate: &'a v8::Isolate, module_name: &str) -> Option<&'a str> {
+    let nix_module_sources = isolate.get_slot::<NixModuleSources>()?;
+    let module_source = nix_module_sources.modules.get(module_name)?;
+    Some(&module_source.nix_source)
+}
+
+/// Returns the file with the given canonical path if it's been imported
+/// during the current evaluation.
+pub fn get_imported_file(
+    scope: &mut v8::HandleScope,
+    canonical_path: &Path,
+) -> Option<ImportedFile> {
+    scope
+        .get_slot::<ImportCache>()
+        .and_then(|import_cache| import_cache.files.get(canonical_path))
+        .cloned()
+}
+
+/// Remembers the imported file until the end of the current evaluation.
+pub fn set_imported_file(
+    scope: &mut v8::HandleScope,
+    canonical_path: PathBuf,
+    imported_file: ImportedFile,
+) {
+    if scope.get_slot::<ImportCache>().is_none() {
+        scope.set_slot(ImportCache::default());
+    }
+    scope
+        .get_slot_mut::<ImportCache>()
+        .unwrap()
+        .files
+        .insert(canonical_path, imported_file);
+}
+
+/// Returns the module with the given specifier. Nix files are read from the
+/// isolate's [`NixFs`](super::fs::NixFs), transpiled and compiled the first
+/// time they're resolved.
+pub fn resolve_module<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    specifier: &str,
+) -> Result<v8::Local<'s, v8::Module>, NixError> {
+    let registered_module = scope
+        .get_slot::<ModuleMap>()
+        .and_then(|module_map| module_map.modules.get(specifier))
+        .cloned();
+    if let Some(module) = registered_module {
+        return Ok(v8::Local::new(scope, module));
+    }
+
+    if !Path::new(specifier).is_absolute() {
+        return Err(format!(
+            "Cannot resolve the module '{specifier}'. Only '{NIXJS_RT_MODULE}' and absolute paths can be imported."
+        )
+        .into());
+    }
+    let nix_source = get_fs(scope)
+        .read_to_string(Path::new(specifier))
+        .map_err(|err| format!("Failed to read the file '{specifier}'. Error: {err}."))?;
+    let module = compile_nix_module(scope, &nix_source, specifier)?;
+    register_module(scope, specifier, module);
+    Ok(module)
+}
+
+/// Transpiles the Nix expression and compiles the result into an ES module.
+/// Both steps are skipped for expressions found in the isolate's
+/// [`EvalCache`].
+pub fn compile_nix_module<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    nix_expr: &str,
+    module_name: &str,
+) -> Result<v8::Local<'s, v8::Module>, NixError> {
+    let eval_cache = scope.get_slot::<EvalCache>().cloned();
+    if let Some(eval_cache) = &eval_cache {
+        if let Some(cached_module) = eval_cache.get(nix_expr) {
+            register_nix_module_source(scope, module_name, nix_expr, cached_module.source_map);
+            let try_catch = &mut v8::TryCatch::new(scope);
+            if let Some(module) = compile_js_module(
+                try_catch,
+                nix_expr,
+                module_name,
+                &cached_module.js_source,
+                cached_module.code_cache,
+                Some(eval_cache),
+            ) {
+                return Ok(module);
+            }
+            // The cached entry is corrupt, e.g. truncated. It's replaced with
+            // freshly transpiled JavaScript below.
+            try_catch.reset();
+            eval_cache.remove(nix_expr);
+        }
+    }
+
+    let (source_str, source_map) =
+        emit_module_with_source_map(nix_expr).map_err(|err| err.in_file(module_name))?;
+    if let Some(eval_cache) = &eval_cache {
+        eval_cache.set_js_source(nix_expr, &source_str, &source_map);
+    }
+    register_nix_module_source(scope, module_name, nix_expr, source_map);
+    compile_js_module(
+        scope,
+        nix_expr,
+        module_name,
+        &source_str,
+        None,
+        eval_cache.as_ref(),
+    )
+    .ok_or_else(|| "Failed to compile the module.".into())
+}
+
+/// Compiles the JavaScript transpiled from the Nix expression into an ES
+/// module, using the code cache if there is one, and stores V8's code cache
+/// of the module in the [`EvalCache`] unless the given one was accepted.
+fn compile_js_module<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    nix_expr: &str,
+    module_name: &str,
+    source_str: &str,
+    code_cache: Option<Vec<u8>>,
+    eval_cache: Option<&EvalCache>,
+) -> Option<v8::Local<'s, v8::Module>> {
+    let cached_data = code_cache.as_deref().map(v8::CachedData::new);
+    let compile_options = match cached_data {
+        Some(_) => v8::script_compiler::CompileOptions::ConsumeCodeCache,
+        None => v8::script_compiler::CompileOptions::NoCompileOptions,
+    };
+    let mut module_source_v8 = to_v8_source(scope, source_str, module_name, cached_data);
+    let module = v8::script_compiler::compile_module2(
+        scope,
+        &mut module_source_v8,
+        compile_options,
+        v8::script_compiler::NoCacheReason::NoReason,
+    )?;
+
+    let code_cache_accepted = module_source_v8
+        .get_cached_data()
+        .is_some_and(|code_cache| !code_cache.rejected());
+    if let Some(eval_cache) = eval_cache.filter(|_| !code_cache_accepted) {
+        if let Some(code_cache) = module.get_unbound_module_script(scope).create_code_cache() {
+            eval_cache.set_code_cache(nix_expr, &code_cache);
+        }
+    }
+    Some(module)
+}
+
+/// Rebuilds the Nix stack trace of the exception from the V8 stack trace that
+/// was captured when the exception was created. Only frames in transpiled Nix
+/// modules are kept, and they are ordered from the outermost to the innermost.
+pub fn nix_stack_trace(
+    scope: &mut v8::HandleScope,
+    exception: v8::Local<v8::Value>,
+) -> Vec<NixStackFrame> {
+    let Some(stack_trace) = v8::Exception::get_stack_trace(scope, exception) else {
+        return Vec::new();
+    };
+    let mut trace: Vec<NixStackFrame> = Vec::new();
+    for frame_idx in (0..stack_trace.get_frame_count()).rev() {
+        let Some(frame) = stack_trace.get_frame(scope, frame_idx) else {
+            continue;
+        };
+        let Some(script_name) = frame.get_script_name(scope) else {
+            continue;
+        };
+        let module_name = script_name.to_rust_string_lossy(scope);
+        let Some(module_source) = scope
+            .get_slot::<NixModuleSources>()
+            .and_then(|nix_module_sources| nix_module_sources.modules.get(&module_name))
+        else {
+            continue;
+        };
+        // V8 lines and columns are one-based, whereas source maps are zero-based.
+        let Some((line, column)) = module_source.source_map.original_position(
+            frame.get_line_number().saturating_sub(1) as u32,
+            frame.get_column().saturating_sub(1) as u32,
+        ) else {
+            continue;
+        };
+        let description = position_to_offset(&module_source.nix_source, line, column).map_or_else(
+            || "while evaluating an expression".to_owned(),
+            |offset| describe_expr_at(&module_source.nix_source, offset),
+        );
+        let nix_frame = NixStackFrame {
+            file: module_name,
+            line: line as usize + 1,
+            column: column as usize + 1,
+            description,
+        };
+        if trace.last() != Some(&nix_frame) {
+            trace.push(nix_frame);
+        }
+    }
+    trace
+}
+
+pub fn resolve_module_callback<'a>(
+    context: v8::Local<'a, v8::Context>,
+    specifier: v8::Local<'a, v8::String>,
+    _: v8::Local<'a, v8::FixedArray>,
+    _: v8::Local<'a, v8::Module>,
+) -> Option<v8::Local<'a, v8::Module>> {
+    // SAFETY: V8 calls this callback from within the given context.
+    let scope = &mut unsafe { v8::CallbackScope::new(context) };
+    let specifier = specifier.to_rust_string_lossy(scope);
+    match resolve_module(scope, &specifier) {
+        Ok(module) => Some(module),
+        Err(err) => {
+            let message = v8::String::new(scope, &err.to_string()).unwrap();
+            let exception = v8::Exception::error(scope, message);
+            scope.throw_exception(exception);
+            None
+        }
+    }
+}
+
+fn register_module(scope: &mut v8::HandleScope, specifier: &str, module: v8::Local<v8::Module>) {
+    let module = v8::Global::new(scope, module);
+    if scope.get_slot::<ModuleMap>().is_none() {
+        scope.set_slot(ModuleMap::default());
+    }
+    scope
+        .get_slot_mut::<ModuleMap>()
+        .unwrap()
+        .modules
+        .insert(specifier.to_owned(), module);
+}
+
+fn register_nix_module_source(
+    scope: &mut v8::HandleScope,
+    module_name: &str,
+    nix_source: &str,
+    source_map: SourceMap,
+) {
+    if scope.get_slot::<NixModuleSources>().is_none() {
+        scope.set_slot(NixModuleSources::default());
+    }
+    scope
+        .get_slot_mut::<NixModuleSources>()
+        .unwrap()
+        .modules
+        .insert(
+            module_name.to_owned(),
+            NixModuleSource {
+                nix_source: nix_source.to_owned(),
+                source_map,
+            },
+        );
+}
+
+/// Exports all values of the global variable `n` from the module `nixjs-rt`.
+fn nixjs_rt_module_evaluation_steps<'a>(
+    context: v8::Local<'a, v8::Context>,
+    module: v8::Local<v8::Module>,
+) -> Option<v8::Local<'a, v8::Value>> {
+    // SAFETY: V8 calls this callback from within the given context.
+    let scope = &mut unsafe { v8::CallbackScope::new(context) };
+    let nixjs_rt_obj = get_nixjs_rt(scope).ok()?;
+    let export_names = nixjs_rt_obj.get_own_property_names(scope, Default::default())?;
+    for idx in 0..export_names.length() {
+        let export_name: v8::Local<v8::String> =
+            export_names.get_index(scope, idx)?.try_into().ok()?;
+        let export_value = nixjs_rt_obj.get(scope, export_name.into())?;
+        module.set_synthetic_module_export(scope, export_name, export_value)?;
+    }
+
+    // Module evaluation returns a promise because of top-level await.
+    let resolver = v8::PromiseResolver::new(scope)?;
+    let undefined = v8::undefined(scope);
+    resolver.resolve(scope, undefined.into());
+    Some(resolver.get_promise(scope).into())
+}
+
+fn new_script_origin<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    resource_name: &str,
+    source_map_url: &str,
+) -> v8::ScriptOrigin<'s> {
+    let resource_name_v8_str = v8::String::new(scope, resource_name).unwrap();
+    let resource_line_offset = 0;
+    let resource_column_offset = 0;
+    let resource_is_shared_cross_origin = true;
+    let script_id = 123;
+    let source_map_url = v8::String::new(scope, source_map_url).unwrap();
+    let resource_is_opaque = false;
+    let is_wasm = false;
+    let is_module = true;
+    v8::ScriptOrigin::new(
+        scope,
+        resource_name_v8_str.into(),
+        resource_line_offset,
+        resource_column_offset,
+        resource_is_shared_cross_origin,
+        script_id,
+        source_map_url.into(),
+        resource_is_opaque,
+        is_wasm,
+        is_module,
+    )
+}
+
+fn to_v8_source(
+    scope: &mut v8::HandleScope,
+    js_code: &str,
+    source_path: &str,
+    cached_data: Option<v8::UniqueRef<v8::CachedData>>,
+) -> v8::script_compiler::Source {
+    let code = v8::String::new(scope, js_code).unwrap();
+    let origin = new_script_origin(scope, source_path, &format!("file://{source_path}.map"));
+    match cached_data {
+        Some(cached_data) => {
+            v8::script_compiler::Source::new_with_cached_data(code, Some(&origin), cached_data)
+        }
+        None => v8::script_compiler::Source::new(code, Some(&origin)),
+    }
+}
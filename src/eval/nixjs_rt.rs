@@ -0,0 +1,82 @@
+//! Loads the Nix runtime (`nixjs-rt`) into a V8 context. The build script also
+//! uses this module to create the startup snapshot, so it may only depend on V8.
+
+use deno_core::v8;
+
+/// The name of the global variable through which transpiled Nix code accesses
+/// the Nix runtime.
+const NIXJS_RT_GLOBAL: &str = "n";
+
+/// Executes the Nix runtime module and stores its exports in the global
+/// variable `n` of the current context.
+pub fn load_nixjs_rt<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    nixjs_rt_src: &str,
+) -> Result<v8::Local<'s, v8::Object>, String> {
+    let code = v8::String::new(scope, nixjs_rt_src)
+        .ok_or("The Nix runtime source is too large for a JavaScript string.")?;
+    let origin = nixjs_rt_origin(scope);
+    let source = v8::script_compiler::Source::new(code, Some(&origin));
+    let module = v8::script_compiler::compile_module(scope, source)
+        .ok_or("Failed to compile the Nix runtime module.")?;
+
+    module
+        .instantiate_module(scope, resolve_no_modules)
+        .ok_or("Failed to instantiate the Nix runtime module.")?;
+    module
+        .evaluate(scope)
+        .ok_or("Failed to evaluate the Nix runtime module.")?;
+
+    let nixjs_rt_obj = module
+        .get_module_namespace()
+        .to_object(scope)
+        .ok_or("Failed to get the namespace of the Nix runtime module.")?;
+
+    let global = scope.get_current_context().global(scope);
+    let nixrt_attr = v8::String::new(scope, NIXJS_RT_GLOBAL).unwrap();
+    global
+        .set(scope, nixrt_attr.into(), nixjs_rt_obj.into())
+        .ok_or("Failed to set the Nix runtime global variable.")?;
+
+    Ok(nixjs_rt_obj)
+}
+
+/// Gets the Nix runtime from the global variable `n` of the current context,
+/// e.g. a context restored from the startup snapshot.
+pub fn get_nixjs_rt<'s>(
+    scope: &mut v8::HandleScope<'s>,
+) -> Result<v8::Local<'s, v8::Object>, String> {
+    let global = scope.get_current_context().global(scope);
+    let nixrt_attr = v8::String::new(scope, NIXJS_RT_GLOBAL).unwrap();
+    global
+        .get(scope, nixrt_attr.into())
+        .and_then(|nixjs_rt| nixjs_rt.to_object(scope))
+        .ok_or_else(|| "The Nix runtime is not loaded in this context.".to_owned())
+}
+
+fn nixjs_rt_origin<'s>(scope: &mut v8::HandleScope<'s>) -> v8::ScriptOrigin<'s> {
+    let resource_name = v8::String::new(scope, "nixjs-rt").unwrap();
+    let source_map_url = v8::undefined(scope);
+    v8::ScriptOrigin::new(
+        scope,
+        resource_name.into(),
+        0,
+        0,
+        false,
+        0,
+        source_map_url.into(),
+        false,
+        false,
+        true,
+    )
+}
+
+/// The Nix runtime is bundled into a single module and doesn't import anything.
+fn resolve_no_modules<'a>(
+    _: v8::Local<'a, v8::Context>,
+    _: v8::Local<'a, v8::String>,
+    _: v8::Local<'a, v8::FixedArray>,
+    _: v8::Local<'a, v8::Module>,
+) -> Option<v8::Local<'a, v8::Module>> {
+    None
+}
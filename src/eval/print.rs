@@ -0,0 +1,148 @@
+use std::fmt::{self, Display, Formatter, Write};
+
+use super::types::Value;
+
+/// Prints values the same way `nix eval` does. The alternate flag (`{:#}`)
+/// pretty-prints nested lists and attrsets over multiple indented lines.
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        print_value(self, f, f.alternate(), 0)
+    }
+}
+
+const INDENT: &str = "  ";
+
+fn print_value(value: &Value, f: &mut Formatter<'_>, pretty: bool, depth: usize) -> fmt::Result {
+    match value {
+        Value::AttrSet(attrs) => {
+            if attrs.is_empty() {
+                return write!(f, "{{ }}");
+            }
+            let multiline = pretty && should_print_multiline(attrs.values());
+            write!(f, "{{")?;
+            for (attr_name, value) in attrs {
+                print_separator(f, multiline, depth + 1)?;
+                print_attr_name(attr_name, f)?;
+                write!(f, " = ")?;
+                print_value(value, f, pretty, depth + 1)?;
+                write!(f, ";")?;
+            }
+            print_separator(f, multiline, depth)?;
+            write!(f, "}}")
+        }
+        Value::Bool(boolean) => write!(f, "{boolean}"),
+        Value::Float(float) => print_float(*float, f),
+        Value::Int(int) => write!(f, "{int}"),
+        Value::Lambda => write!(f, "<LAMBDA>"),
+        Value::List(vector) => {
+            if vector.is_empty() {
+                return write!(f, "[ ]");
+            }
+            let multiline = pretty && should_print_multiline(vector.iter());
+            write!(f, "[")?;
+            for value in vector {
+                print_separator(f, multiline, depth + 1)?;
+                print_value(value, f, pretty, depth + 1)?;
+            }
+            print_separator(f, multiline, depth)?;
+            write!(f, "]")
+        }
+        Value::Null => write!(f, "null"),
+        Value::Path(path) => write!(f, "{path}"),
+        Value::PrimOp(_) => write!(f, "<PRIMOP>"),
+        Value::Str(string) => print_string(&string.value, f),
+    }
+}
+
+/// Lists and attrsets are printed on multiple lines if they have more than one
+/// item or if their only item is a list or an attrset.
+fn should_print_multiline<'a>(mut values: impl ExactSizeIterator<Item = &'a Value>) -> bool {
+    values.len() > 1 || matches!(values.next(), Some(Value::AttrSet(_) | Value::List(_)))
+}
+
+fn print_separator(f: &mut Formatter<'_>, multiline: bool, depth: usize) -> fmt::Result {
+    if multiline {
+        write!(f, "\n{}", INDENT.repeat(depth))
+    } else {
+        write!(f, " ")
+    }
+}
+
+fn print_attr_name(attr_name: &str, f: &mut Formatter<'_>) -> fmt::Result {
+    if is_valid_identifier(attr_name) {
+        write!(f, "{attr_name}")
+    } else {
+        print_string(attr_name, f)
+    }
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "assert", "else", "if", "in", "inherit", "let", "rec", "then", "with",
+    ];
+    let mut chars = name.chars();
+    let Some(first_char) = chars.next() else {
+        return false;
+    };
+    (first_char.is_ascii_alphabetic() || first_char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || matches!(char, '_' | '\'' | '-'))
+        && !KEYWORDS.contains(&name)
+}
+
+fn print_string(string: &str, f: &mut Formatter<'_>) -> fmt::Result {
+    f.write_char('"')?;
+    let mut chars = string.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '$' if chars.peek() == Some(&'{') => f.write_str("\\$")?,
+            char => f.write_char(char)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Prints floats with six significant digits, like C++ streams do by default.
+fn print_float(float: f64, f: &mut Formatter<'_>) -> fmt::Result {
+    if float.is_nan() || float.is_infinite() || float == 0.0 {
+        let sign = if float.is_sign_negative() { "-" } else { "" };
+        let magnitude = if float.is_nan() {
+            "nan"
+        } else if float.is_infinite() {
+            "inf"
+        } else {
+            "0"
+        };
+        return write!(f, "{sign}{magnitude}");
+    }
+
+    // Rounds the float to six significant digits, e.g. `1.25000e1`.
+    let scientific = format!("{float:.5e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+
+    if !(-4..6).contains(&exponent) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        return write!(
+            f,
+            "{}e{sign}{:02}",
+            trim_fraction_zeros(mantissa),
+            exponent.abs()
+        );
+    }
+
+    let decimals = (5 - exponent) as usize;
+    write!(f, "{}", trim_fraction_zeros(&format!("{float:.decimals$}")))
+}
+
+fn trim_fraction_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
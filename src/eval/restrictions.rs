@@ -0,0 +1,95 @@
+//! Restricts what an evaluation can access outside of the evaluated code.
+//!
+//! In restricted and pure mode, the host functions only access paths under
+//! the allowed prefixes, which are given with `-I` on the command line. Both
+//! modes hide the environment variables of the host, and pure mode
+//! additionally hides the current time.
+
+use std::path::{Path, PathBuf};
+
+use deno_core::v8;
+
+use super::fs::{normalize_path, NixFs};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvalMode {
+    /// Any path and the environment may be accessed.
+    #[default]
+    Impure,
+    /// Only paths under the allowed prefixes may be accessed, and the
+    /// environment variables are hidden.
+    Restricted,
+    /// Like [`EvalMode::Restricted`], but the current time is hidden as well.
+    Pure,
+}
+
+impl EvalMode {
+    /// How Nix refers to the mode in error messages.
+    pub fn description(self) -> &'static str {
+        match self {
+            EvalMode::Impure => "impure mode",
+            EvalMode::Restricted => "restricted mode",
+            EvalMode::Pure => "pure evaluation mode",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalRestrictions {
+    pub mode: EvalMode,
+    /// The prefixes of the paths that may be accessed in restricted and pure
+    /// mode.
+    pub allowed_paths: Vec<PathBuf>,
+}
+
+impl EvalRestrictions {
+    /// Whether environment variables are hidden.
+    pub fn is_environment_hidden(&self) -> bool {
+        self.mode != EvalMode::Impure
+    }
+
+    /// Whether the current time is hidden.
+    pub fn is_time_hidden(&self) -> bool {
+        self.mode == EvalMode::Pure
+    }
+
+    /// Whether the absolute path may be accessed. Symlinks and `..`
+    /// components are resolved before the path is checked, so that they can't
+    /// lead outside of the allowed prefixes.
+    pub fn is_path_allowed(&self, fs: &dyn NixFs, path: &Path) -> bool {
+        if self.mode == EvalMode::Impure {
+            return true;
+        }
+        let path = resolve_path(fs, path);
+        self.allowed_paths
+            .iter()
+            .any(|allowed_path| path.starts_with(resolve_path(fs, allowed_path)))
+    }
+}
+
+/// Resolves the nearest existing ancestor of the path and appends the
+/// components that don't exist, so that a missing file behind a symlink is
+/// checked at the symlink's target.
+fn resolve_path(fs: &dyn NixFs, path: &Path) -> PathBuf {
+    for ancestor in path.ancestors() {
+        if let Ok(resolved_ancestor) = fs.canonicalize(ancestor) {
+            let missing_components = path.strip_prefix(ancestor).unwrap_or(Path::new(""));
+            return normalize_path(&resolved_ancestor.join(missing_components));
+        }
+    }
+    normalize_path(path)
+}
+
+/// Restricts the evaluations in the isolate.
+pub fn set_restrictions(isolate: &mut v8::Isolate, restrictions: EvalRestrictions) {
+    isolate.set_slot(restrictions);
+}
+
+/// Returns the restrictions of the evaluations in the isolate. There are none
+/// unless they were set.
+pub fn get_restrictions(isolate: &v8::Isolate) -> EvalRestrictions {
+    isolate
+        .get_slot::<EvalRestrictions>()
+        .cloned()
+        .unwrap_or_default()
+}
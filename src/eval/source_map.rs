@@ -0,0 +1,227 @@
+use serde_json::json;
+
+/// A source map (version 3) that maps positions in the transpiled JavaScript
+/// back to positions in the original Nix source.
+///
+/// Lines and columns are zero-based. Columns are counted in UTF-16 code units,
+/// as is expected by V8 and browser dev tools.
+#[derive(Debug, Default, PartialEq)]
+pub struct SourceMap {
+    pub mappings: Vec<Mapping>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mapping {
+    pub generated_line: u32,
+    pub generated_column: u32,
+    pub original_line: u32,
+    pub original_column: u32,
+}
+
+impl SourceMap {
+    /// Builds a source map from pairs of byte offsets into the generated
+    /// JavaScript and the original Nix source.
+    pub fn from_offsets(js_source: &str, nix_source: &str, offsets: &[(usize, usize)]) -> Self {
+        let js_lines = LineIndex::new(js_source);
+        let nix_lines = LineIndex::new(nix_source);
+        let mut mappings: Vec<Mapping> = offsets
+            .iter()
+            .map(|(js_offset, nix_offset)| {
+                let (generated_line, generated_column) = js_lines.position(js_source, *js_offset);
+                let (original_line, original_column) = nix_lines.position(nix_source, *nix_offset);
+                Mapping {
+                    generated_line,
+                    generated_column,
+                    original_line,
+                    original_column,
+                }
+            })
+            .collect();
+        mappings.sort_by_key(|mapping| (mapping.generated_line, mapping.generated_column));
+        mappings.dedup_by_key(|mapping| (mapping.generated_line, mapping.generated_column));
+        SourceMap { mappings }
+    }
+
+    /// Finds the original Nix position of the given generated JavaScript
+    /// position. This is the position of the closest mapping that starts at or
+    /// before the given position on the same line.
+    pub fn original_position(
+        &self,
+        generated_line: u32,
+        generated_column: u32,
+    ) -> Option<(u32, u32)> {
+        self.mappings
+            .iter()
+            .take_while(|mapping| {
+                (mapping.generated_line, mapping.generated_column)
+                    <= (generated_line, generated_column)
+            })
+            .filter(|mapping| mapping.generated_line == generated_line)
+            .last()
+            .map(|mapping| (mapping.original_line, mapping.original_column))
+    }
+
+    /// Encodes the `mappings` field of the source map.
+    pub fn encode_mappings(&self) -> String {
+        let mut encoded = String::new();
+        let mut current_line = 0;
+        let mut prev_generated_column = 0;
+        let mut prev_original_line = 0;
+        let mut prev_original_column = 0;
+        let mut is_first_segment_on_line = true;
+        for mapping in &self.mappings {
+            while current_line < mapping.generated_line {
+                encoded.push(';');
+                current_line += 1;
+                prev_generated_column = 0;
+                is_first_segment_on_line = true;
+            }
+            if !is_first_segment_on_line {
+                encoded.push(',');
+            }
+            is_first_segment_on_line = false;
+            encode_vlq(
+                mapping.generated_column as i64 - prev_generated_column as i64,
+                &mut encoded,
+            );
+            // All mappings point into the single source with index 0.
+            encode_vlq(0, &mut encoded);
+            encode_vlq(
+                mapping.original_line as i64 - prev_original_line as i64,
+                &mut encoded,
+            );
+            encode_vlq(
+                mapping.original_column as i64 - prev_original_column as i64,
+                &mut encoded,
+            );
+            prev_generated_column = mapping.generated_column;
+            prev_original_line = mapping.original_line;
+            prev_original_column = mapping.original_column;
+        }
+        encoded
+    }
+
+    /// Decodes the `mappings` field of a source map that was encoded with
+    /// [`SourceMap::encode_mappings`]. Returns `None` if it's malformed.
+    pub fn decode_mappings(encoded: &str) -> Option<Self> {
+        let mut mappings = Vec::new();
+        let mut prev_original_line = 0;
+        let mut prev_original_column = 0;
+        for (generated_line, line_segments) in encoded.split(';').enumerate() {
+            let mut prev_generated_column = 0;
+            for segment in line_segments
+                .split(',')
+                .filter(|segment| !segment.is_empty())
+            {
+                let mut chars = segment.chars();
+                let generated_column = prev_generated_column + decode_vlq(&mut chars)?;
+                let _source_idx = decode_vlq(&mut chars)?;
+                let original_line = prev_original_line + decode_vlq(&mut chars)?;
+                let original_column = prev_original_column + decode_vlq(&mut chars)?;
+                mappings.push(Mapping {
+                    generated_line: generated_line as u32,
+                    generated_column: generated_column.try_into().ok()?,
+                    original_line: original_line.try_into().ok()?,
+                    original_column: original_column.try_into().ok()?,
+                });
+                prev_generated_column = generated_column;
+                prev_original_line = original_line;
+                prev_original_column = original_column;
+            }
+        }
+        Some(SourceMap { mappings })
+    }
+
+    /// Serializes this source map into the version 3 JSON format.
+    pub fn to_json(&self, file: &str, source: &str, source_content: &str) -> String {
+        json!({
+            "version": 3,
+            "file": file,
+            "sources": [source],
+            "sourcesContent": [source_content],
+            "names": [],
+            "mappings": self.encode_mappings(),
+        })
+        .to_string()
+    }
+}
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Appends the base64 VLQ encoding of the given number.
+pub fn encode_vlq(value: i64, out: &mut String) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.push(BASE64_CHARS[digit as usize] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Decodes the next base64 VLQ encoded number.
+pub fn decode_vlq(chars: &mut impl Iterator<Item = char>) -> Option<i64> {
+    let mut vlq: i64 = 0;
+    let mut shift = 0;
+    loop {
+        let char = chars.next()?;
+        let digit = BASE64_CHARS.iter().position(|c| *c as char == char)? as i64;
+        vlq |= (digit & 0b11111) << shift;
+        shift += 5;
+        if digit & 0b100000 == 0 {
+            break;
+        }
+        if shift > 60 {
+            return None;
+        }
+    }
+    Some(if vlq & 1 == 1 { -(vlq >> 1) } else { vlq >> 1 })
+}
+
+/// Converts a zero-based line and UTF-16 column in the text into a byte
+/// offset. Returns `None` if the position is outside the text.
+pub fn position_to_offset(text: &str, line: u32, column: u32) -> Option<usize> {
+    let line_start = LineIndex::new(text)
+        .line_starts
+        .get(line as usize)
+        .copied()?;
+    let mut utf16_column = 0;
+    for (offset, char) in text[line_start..].char_indices() {
+        if utf16_column >= column as usize || char == '\n' {
+            return Some(line_start + offset);
+        }
+        utf16_column += char.len_utf16();
+    }
+    Some(text.len())
+}
+
+/// Converts byte offsets into zero-based lines and UTF-16 columns.
+struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(idx, _)| idx + 1));
+        LineIndex { line_starts }
+    }
+
+    fn position(&self, text: &str, offset: usize) -> (u32, u32) {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let column: usize = text[self.line_starts[line]..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        (line as u32, column as u32)
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use deno_core::v8;
 
@@ -9,14 +9,56 @@ use super::{
 
 #[derive(Debug, PartialEq)]
 pub enum Value {
-    AttrSet(HashMap<String, Value>),
+    AttrSet(BTreeMap<String, Value>),
     Bool(bool),
     Float(f64),
     Int(i64),
     Lambda,
     List(Vec<Value>),
+    Null,
     Path(String),
-    Str(String),
+    /// A lambda built into the runtime, e.g. `builtins.add`, with its name.
+    PrimOp(String),
+    Str(NixString),
+}
+
+/// A Nix string together with its context, i.e. the paths that were coerced
+/// into it, e.g. through `"${./foo}"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NixString {
+    pub value: String,
+    pub context: BTreeSet<String>,
+}
+
+impl From<String> for NixString {
+    fn from(value: String) -> Self {
+        NixString {
+            value,
+            context: BTreeSet::new(),
+        }
+    }
+}
+
+impl From<&str> for NixString {
+    fn from(value: &str) -> Self {
+        value.to_owned().into()
+    }
+}
+
+impl Value {
+    pub fn type_kind(&self) -> NixTypeKind {
+        match self {
+            Value::AttrSet(_) => NixTypeKind::Set,
+            Value::Bool(_) => NixTypeKind::Bool,
+            Value::Float(_) => NixTypeKind::Float,
+            Value::Int(_) => NixTypeKind::Int,
+            Value::Lambda | Value::PrimOp(_) => NixTypeKind::Lambda,
+            Value::List(_) => NixTypeKind::List,
+            Value::Null => NixTypeKind::Null,
+            Value::Path(_) => NixTypeKind::Path,
+            Value::Str(_) => NixTypeKind::String,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -32,6 +74,23 @@ pub enum NixTypeKind {
     Set,
 }
 
+impl std::fmt::Display for NixTypeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let human_readable = match self {
+            NixTypeKind::Bool => "a boolean",
+            NixTypeKind::Float => "a float",
+            NixTypeKind::Int => "an int",
+            NixTypeKind::List => "a list",
+            NixTypeKind::Null => "a null",
+            NixTypeKind::String => "a string",
+            NixTypeKind::Path => "a path",
+            NixTypeKind::Lambda => "a lambda",
+            NixTypeKind::Set => "a set",
+        };
+        write!(f, "{human_readable}")
+    }
+}
+
 pub type EvalResult = Result<Value, NixError>;
 
 pub fn js_value_to_nix(
@@ -66,13 +125,20 @@ pub fn js_value_to_nix(
     if let Some(value) = from_js_path(scope, nixrt, js_value)? {
         return Ok(value);
     }
+    if let Some(value) = from_js_null(scope, nixrt, js_value)? {
+        return Ok(value);
+    }
+    if let Some(value) = from_js_primop(scope, nixrt, js_value)? {
+        return Ok(value);
+    }
     if let Some(value) = from_js_lambda(scope, nixrt, js_value)? {
         return Ok(value);
     }
-    todo!(
-        "js_value_to_nix: {:?}",
+    Err(format!(
+        "Could not convert the JavaScript value '{}' into a Nix value.",
         js_value.to_rust_string_lossy(scope),
     )
+    .into())
 }
 
 fn from_js_int(
@@ -104,9 +170,26 @@ fn from_js_string(
         let value_js_string: v8::Local<v8::String> = value.try_into().map_err(|err| {
             format!("Expected a string value. Internal conversion error: {err:?}")
         })?;
-        return Ok(Some(Value::Str(
-            value_js_string.to_rust_string_lossy(scope),
-        )));
+        let context = try_get_js_object_key(scope, js_value, "context")?.ok_or_else(|| {
+            "Internal error: could not find the `context` property on the NixString object."
+                .to_string()
+        })?;
+        let context_set: v8::Local<v8::Set> = context.try_into().map_err(|err| {
+            format!(
+                "Expected `context` to be a Set on the NixString object. Internal conversion error: {err:?}"
+            )
+        })?;
+        let context_array = context_set.as_array(scope);
+        let context = (0..context_array.length())
+            .filter_map(|index| {
+                let path = context_array.get_index(scope, index)?;
+                Some(path.to_rust_string_lossy(scope))
+            })
+            .collect();
+        return Ok(Some(Value::Str(NixString {
+            value: value_js_string.to_rust_string_lossy(scope),
+            context,
+        })));
     }
     Ok(None)
 }
@@ -241,6 +324,31 @@ fn from_js_path(
     Ok(Some(Value::Path(path.to_rust_string_lossy(scope))))
 }
 
+fn from_js_null(
+    scope: &mut v8::HandleScope<'_>,
+    nixrt: &v8::Local<v8::Object>,
+    js_value: &v8::Local<v8::Value>,
+) -> Result<Option<Value>, NixError> {
+    if !is_nixrt_type(scope, nixrt, js_value, "NixNull")? {
+        return Ok(None);
+    }
+    Ok(Some(Value::Null))
+}
+
+fn from_js_primop(
+    scope: &mut v8::HandleScope<'_>,
+    nixrt: &v8::Local<v8::Object>,
+    js_value: &v8::Local<v8::Value>,
+) -> Result<Option<Value>, NixError> {
+    if !is_nixrt_type(scope, nixrt, js_value, "PrimOp")? {
+        return Ok(None);
+    }
+    let Some(name) = try_get_js_object_key(scope, js_value, "name")? else {
+        return Ok(None);
+    };
+    Ok(Some(Value::PrimOp(name.to_rust_string_lossy(scope))))
+}
+
 fn from_js_lambda(
     scope: &mut v8::HandleScope<'_>,
     nixrt: &v8::Local<v8::Object>,
@@ -260,7 +368,9 @@ fn js_value_as_nix_array(
     let length = js_array.length();
     let mut rust_array = Vec::with_capacity(length as usize);
     for idx in 0..length {
-        let js_element = js_array.get_index(scope, idx).unwrap();
+        let js_element = js_array
+            .get_index(scope, idx)
+            .ok_or("Unexpected index out-of-bounds.")?;
         match js_value_to_nix(scope, nixrt, &js_element) {
             Ok(value) => rust_array.push(value),
             err => return err,
@@ -274,19 +384,21 @@ fn js_map_as_attrset(
     nixrt: &v8::Local<v8::Object>,
     js_map: &v8::Local<v8::Map>,
 ) -> EvalResult {
-    let mut map: HashMap<String, Value> = HashMap::new();
+    let mut map: BTreeMap<String, Value> = BTreeMap::new();
     let js_map_array = js_map.as_array(scope);
     for idx in 0..js_map_array.length() / 2 {
         let key_idx = idx * 2;
         let value_idx = key_idx + 1;
         let key: v8::Local<v8::String> = js_map_array
             .get_index(scope, key_idx)
-            .expect("Unexpected index out-of-bounds.")
+            .ok_or("Unexpected index out-of-bounds.")?
             .try_into()
-            .expect("Attr names must be strings.");
+            .map_err(|err| {
+                format!("Attr names must be strings. Internal conversion error: {err:?}")
+            })?;
         let value = js_map_array
             .get_index(scope, value_idx)
-            .expect("Unexpected index out-of-bounds.");
+            .ok_or("Unexpected index out-of-bounds.")?;
         map.insert(
             key.to_rust_string_lossy(scope),
             js_value_to_nix(scope, nixrt, &value)?,
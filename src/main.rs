@@ -1,11 +1,15 @@
 use clap::Command;
 use rix::cmd;
+use rix::eval::error::NixError;
 use std::process::ExitCode;
 
 fn main() -> ExitCode {
     let mut cmd = Command::new("rix")
         .version("0.0.1")
-        .about("Rix is another nix.");
+        .about("Rix is another nix.")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(cmd::color_arg());
 
     let subcommands = &[&cmd::eval::cmd(), &cmd::transpile::cmd()];
 
@@ -19,9 +23,10 @@ fn main() -> ExitCode {
 fn dispatch_cmd(parsed_args: &clap::ArgMatches, subcommands: &[&cmd::RixSubCommand]) -> ExitCode {
     for subcommand in subcommands {
         if let Some(subcommand_args) = parsed_args.subcommand_matches(subcommand.name) {
+            cmd::set_color_mode(subcommand_args);
             return (subcommand.handler)(subcommand_args)
-                .map_or_else(|err| err, |_| ExitCode::SUCCESS);
+                .map_or_else(cmd::ErrorCategory::exit_code, |_| ExitCode::SUCCESS);
         }
     }
-    cmd::print_and_err("operation not supported".into())
+    cmd::print_and_err(NixError::usage("operation not supported".to_owned())).exit_code()
 }
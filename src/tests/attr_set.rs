@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::{
     eval::{
@@ -10,18 +10,34 @@ use crate::{
 
 #[test]
 fn eval_attrset_literal() {
-    assert_eq!(eval_ok("{}"), Value::AttrSet(HashMap::new()));
+    assert_eq!(eval_ok("{}"), Value::AttrSet(BTreeMap::new()));
     assert_eq!(
         eval_ok("{a = 1;}"),
-        Value::AttrSet(HashMap::from([("a".to_owned(), Value::Int(1))]))
+        Value::AttrSet(BTreeMap::from([("a".to_owned(), Value::Int(1))]))
+    );
+}
+
+#[test]
+fn eval_attrset_order() {
+    let attrset_expr = r#"{ b = 1; a = 2; "B" = 3; "a b" = 4; }"#;
+    let Value::AttrSet(attrs) = eval_ok(attrset_expr) else {
+        panic!("expected an attrset");
+    };
+    let attr_names: Vec<Value> = attrs
+        .into_keys()
+        .map(|name| Value::Str(name.into()))
+        .collect();
+    assert_eq!(
+        eval_ok(&format!("builtins.attrNames {attrset_expr}")),
+        Value::List(attr_names)
     );
 }
 
 #[test]
 fn eval_attrset_literal_nesting() {
-    let expected_attrset = Value::AttrSet(HashMap::from([(
+    let expected_attrset = Value::AttrSet(BTreeMap::from([(
         "a".to_owned(),
-        Value::AttrSet(HashMap::from([("b".to_owned(), Value::Int(1))])),
+        Value::AttrSet(BTreeMap::from([("b".to_owned(), Value::Int(1))])),
     )]));
     assert_eq!(eval_ok("{a.b = 1;}"), expected_attrset);
     assert_eq!(eval_ok("{ a = {}; a.b = 1; }"), expected_attrset);
@@ -52,13 +68,13 @@ fn eval_attrset_interpolated_attrs() {
 fn eval_attrset_null_attr() {
     assert_eq!(
         eval_ok(r#"{ ${null} = true; }"#),
-        Value::AttrSet(HashMap::new()),
+        Value::AttrSet(BTreeMap::new()),
     );
     assert_eq!(
         eval_ok(r#"{ a.${null} = true; }"#),
-        Value::AttrSet(HashMap::from([(
+        Value::AttrSet(BTreeMap::from([(
             "a".to_owned(),
-            Value::AttrSet(HashMap::new()),
+            Value::AttrSet(BTreeMap::new()),
         )])),
     );
 }
@@ -82,10 +98,10 @@ fn eval_attrset_non_string_attr() {
 
 #[test]
 fn eval_attrset_update() {
-    assert_eq!(eval_ok("{} // {}"), Value::AttrSet(HashMap::new()));
+    assert_eq!(eval_ok("{} // {}"), Value::AttrSet(BTreeMap::new()));
     assert_eq!(
         eval_ok("{a = 1; b = 2;} // {a = 3; c = 1;}"),
-        Value::AttrSet(HashMap::from([
+        Value::AttrSet(BTreeMap::from([
             ("a".to_owned(), Value::Int(3)),
             ("b".to_owned(), Value::Int(2)),
             ("c".to_owned(), Value::Int(1)),
@@ -121,3 +137,64 @@ fn eval_attrset_select() {
     assert_eq!(eval_ok("{a = 1;}.a"), Value::Int(1));
     assert_eq!(eval_ok("{a = 1;}.b or 2"), Value::Int(2));
 }
+
+#[test]
+fn eval_attrset_inherit() {
+    assert_eq!(
+        eval_ok("let a = 1; in { inherit a; }"),
+        Value::AttrSet(BTreeMap::from([("a".to_owned(), Value::Int(1))]))
+    );
+    assert_eq!(
+        eval_ok(r#"let a = 1; in { inherit "a"; }.a"#),
+        Value::Int(1)
+    );
+    assert_eq!(
+        eval_err("{ inherit a; }.a"),
+        NixErrorKind::CouldntFindVariable {
+            var_name: "a".to_owned()
+        }
+    );
+}
+
+#[test]
+fn eval_recursive_attrset_inherit() {
+    assert_eq!(eval_ok("let a = 1; in rec { inherit a; }.a"), Value::Int(1));
+    assert_eq!(
+        eval_ok("let a = 1; in rec { inherit a; b = a + 1; }.b"),
+        Value::Int(2)
+    );
+}
+
+#[test]
+fn eval_attrset_inherit_from() {
+    assert_eq!(
+        eval_ok("let x = { a = 1; b = 2; }; in { inherit (x) a b; }"),
+        Value::AttrSet(BTreeMap::from([
+            ("a".to_owned(), Value::Int(1)),
+            ("b".to_owned(), Value::Int(2)),
+        ]))
+    );
+    assert_eq!(
+        eval_ok("rec { x = { a = 1; }; inherit (x) a; }.a"),
+        Value::Int(1)
+    );
+    assert_eq!(
+        eval_err("{ inherit ({}) a; }.a"),
+        NixErrorKind::MissingAttribute {
+            attr_path: vec!["a".to_owned()]
+        }
+    );
+}
+
+#[test]
+fn eval_let_inherit() {
+    assert_eq!(eval_ok("let a = 1; in let inherit a; in a"), Value::Int(1));
+    assert_eq!(
+        eval_ok("let x = { a = 1; }; in let inherit (x) a; in a"),
+        Value::Int(1)
+    );
+    assert_eq!(
+        eval_ok("let inherit (x) a; x = { a = 1; }; in a"),
+        Value::Int(1)
+    );
+}
@@ -335,6 +335,73 @@ mod concatStringsSep {
 
 mod convertHash {
     use super::*;
+
+    #[test]
+    fn eval_sri_to_base16() {
+        assert_eq!(
+            eval_ok(
+                r#"builtins.convertHash {
+                    hash = "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+                    toHashFormat = "base16";
+                }"#
+            ),
+            Value::Str("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into())
+        );
+    }
+
+    #[test]
+    fn eval_nix32_to_sri() {
+        assert_eq!(
+            eval_ok(
+                r#"builtins.convertHash {
+                    hash = "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73";
+                    hashAlgo = "sha256";
+                    toHashFormat = "sri";
+                }"#
+            ),
+            Value::Str("sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=".into())
+        );
+    }
+
+    #[test]
+    fn eval_prefixed_to_nix32() {
+        assert_eq!(
+            eval_ok(
+                r#"builtins.convertHash {
+                    hash = "md5:5d41402abc4b2a76b9719d911017c592";
+                    toHashFormat = "nix32";
+                }"#
+            ),
+            Value::Str("4jqlbi14cxf6wpcajbphm40hax".into())
+        );
+    }
+
+    #[test]
+    fn eval_missing_algo() {
+        assert_eq!(
+            eval_err(
+                r#"builtins.convertHash {
+                    hash = "5d41402abc4b2a76b9719d911017c592";
+                    toHashFormat = "nix32";
+                }"#
+            ),
+            NixErrorKind::Other {
+                codename: "builtins-invalid-hash".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn eval_missing_attribute() {
+        assert_eq!(
+            eval_err(
+                r#"builtins.convertHash { hash = "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="; }"#
+            ),
+            NixErrorKind::MissingAttribute {
+                attr_path: vec!["toHashFormat".to_owned()]
+            }
+        );
+    }
 }
 
 mod deepSeq {
@@ -427,10 +494,59 @@ mod getAttr {
 
 mod getContext {
     use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn eval_path_context() {
+        let path = std::env::current_dir().unwrap().join("foo");
+        assert_eq!(
+            eval_ok(r#"builtins.getContext "a${./foo}""#),
+            Value::AttrSet(BTreeMap::from([(
+                path.display().to_string(),
+                Value::AttrSet(BTreeMap::from([("path".to_owned(), Value::Bool(true))]))
+            )]))
+        );
+    }
+
+    #[test]
+    fn eval_no_context() {
+        assert_eq!(
+            eval_ok(r#"builtins.getContext "a""#),
+            Value::AttrSet(BTreeMap::new())
+        );
+    }
 }
 
 mod getEnv {
     use super::*;
+
+    #[test]
+    fn eval_set_variable() {
+        let path = std::env::var("PATH").unwrap_or_default();
+        assert_eq!(
+            eval_ok(r#"builtins.getEnv "PATH""#),
+            Value::Str(path.into())
+        );
+    }
+
+    #[test]
+    fn eval_unset_variable() {
+        assert_eq!(
+            eval_ok(r#"builtins.getEnv "RIX_TESTS_UNSET_VARIABLE""#),
+            Value::Str("".into())
+        );
+    }
+
+    #[test]
+    fn eval_invalid_type() {
+        assert_eq!(
+            eval_err("builtins.getEnv 1"),
+            NixErrorKind::TypeMismatch {
+                expected: vec![NixTypeKind::String],
+                got: NixTypeKind::Int
+            }
+        );
+    }
 }
 
 mod getFlake {
@@ -447,14 +563,96 @@ mod hasAttr {
 
 mod hasContext {
     use super::*;
+
+    #[test]
+    fn eval_has_context() {
+        assert_eq!(eval_ok(r#"builtins.hasContext "a""#), Value::Bool(false));
+        assert_eq!(
+            eval_ok(r#"builtins.hasContext "a${./foo}""#),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.hasContext ("a" + "${./foo}")"#),
+            Value::Bool(true)
+        );
+    }
 }
 
 mod hashFile {
     use super::*;
+
+    #[test]
+    fn eval_file() {
+        assert_eq!(
+            eval_ok(r#"builtins.hashFile "sha256" ./src/tests/fs_tests/hello.txt"#),
+            Value::Str("d9014c4624844aa5bac314773d6b689ad467fa4e1d1a50a1b8a99d5a95f72ff5".into())
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.hashFile "md5" "src/tests/fs_tests/hello.txt""#),
+            Value::Str("746308829575e17c3331bbcb00c0898b".into())
+        );
+    }
+
+    #[test]
+    fn eval_missing_file() {
+        assert!(matches!(
+            eval_err(r#"builtins.hashFile "sha256" ./src/tests/fs_tests/non_existent_file.txt"#),
+            NixErrorKind::Io { .. }
+        ));
+    }
 }
 
 mod hashString {
     use super::*;
+
+    #[test]
+    fn eval_algos() {
+        assert_eq!(
+            eval_ok(r#"builtins.hashString "md5" "hello""#),
+            Value::Str("5d41402abc4b2a76b9719d911017c592".into())
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.hashString "sha1" "hello""#),
+            Value::Str("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".into())
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.hashString "sha256" "hello""#),
+            Value::Str("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".into())
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.hashString "sha512" "hello""#),
+            Value::Str("9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043".into())
+        );
+    }
+
+    #[test]
+    fn eval_empty_string() {
+        assert_eq!(
+            eval_ok(r#"builtins.hashString "sha256" """#),
+            Value::Str("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into())
+        );
+    }
+
+    #[test]
+    fn eval_unknown_algo() {
+        assert_eq!(
+            eval_err(r#"builtins.hashString "sha3" "hello""#),
+            NixErrorKind::Other {
+                codename: "builtins-invalid-hash".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn eval_invalid_type() {
+        assert_eq!(
+            eval_err(r#"builtins.hashString "sha256" 1"#),
+            NixErrorKind::TypeMismatch {
+                expected: vec![NixTypeKind::String],
+                got: NixTypeKind::Int
+            }
+        );
+    }
 }
 
 mod head {
@@ -531,6 +729,29 @@ mod import {
         );
     }
 
+    #[test]
+    fn eval_repeated_import() {
+        assert_eq!(
+            eval_ok(
+                r#"
+                let
+                  dir = ./src/tests/import_tests;
+                  basic = builtins.import ./src/tests/import_tests/basic.nix;
+                in [
+                  basic.data
+                  (builtins.import ./src/tests/import_tests/basic.nix).data
+                  (builtins.import "${dir}/nested/../basic.nix").data
+                ]
+                "#
+            ),
+            Value::List(vec![
+                Value::Str("imported!".into()),
+                Value::Str("imported!".into()),
+                Value::Str("imported!".into()),
+            ])
+        );
+    }
+
     #[test]
     fn eval_relative_string() {
         assert_eq!(
@@ -549,15 +770,44 @@ mod import {
         );
     }
 
-    // TODO: Make this test work.
-    // fn eval_invalid_file() {
-    //     assert_eq!(
-    //         eval_err("builtins.import ./non_existent_file.nix"),
-    //         NixErrorKind::Import {
-    //             path: "./non_existent_file.nix".to_owned()
-    //         }
-    //     );
-    // }
+    #[test]
+    fn eval_invalid_file() {
+        let path = std::env::current_dir()
+            .unwrap()
+            .join("non_existent_file.nix")
+            .to_string_lossy()
+            .into_owned();
+        assert!(matches!(
+            eval_err("builtins.import ./non_existent_file.nix"),
+            NixErrorKind::ImportFailed { path: failed_path, .. } if failed_path == path
+        ));
+    }
+
+    #[test]
+    fn eval_error_in_imported_file() {
+        assert_eq!(
+            eval_err("builtins.import ./src/tests/import_tests/failed-assertion.nix"),
+            NixErrorKind::AssertionFailed {
+                condition: "false".to_owned()
+            }
+        );
+    }
+
+    #[test]
+    fn eval_invalid_syntax() {
+        let invalid_syntax_path = std::env::current_dir()
+            .unwrap()
+            .join("src/tests/import_tests/invalid-syntax.nix");
+        assert_eq!(
+            eval_err("builtins.import ./src/tests/import_tests/invalid-syntax.nix"),
+            NixErrorKind::ParseError {
+                file: invalid_syntax_path.to_string_lossy().into_owned(),
+                line: 1,
+                column: 9,
+                message: "syntax error, unexpected '}', expecting ';'".to_owned()
+            }
+        );
+    }
 }
 
 mod intersectAttrs {
@@ -754,6 +1004,26 @@ mod path {
 
 mod pathExists {
     use super::*;
+
+    #[test]
+    fn eval_existing_path() {
+        assert_eq!(
+            eval_ok("builtins.pathExists ./src/tests/fs_tests/hello.txt"),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval_ok(r#"builtins.pathExists "src/tests/fs_tests/nested""#),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn eval_missing_path() {
+        assert_eq!(
+            eval_ok("builtins.pathExists ./src/tests/fs_tests/non_existent_file.txt"),
+            Value::Bool(false)
+        );
+    }
 }
 
 mod placeholder {
@@ -762,14 +1032,90 @@ mod placeholder {
 
 mod readDir {
     use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn eval_dir() {
+        assert_eq!(
+            eval_ok("builtins.readDir ./src/tests/fs_tests"),
+            Value::AttrSet(BTreeMap::from([
+                ("__proto__".to_owned(), Value::Str("regular".into())),
+                ("hello.txt".to_owned(), Value::Str("regular".into())),
+                ("nested".to_owned(), Value::Str("directory".into())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_missing_dir() {
+        assert!(matches!(
+            eval_err("builtins.readDir ./src/tests/fs_tests/non_existent_dir"),
+            NixErrorKind::Io { path, .. } if path.ends_with("src/tests/fs_tests/non_existent_dir")
+        ));
+    }
 }
 
 mod readFile {
     use super::*;
+
+    #[test]
+    fn eval_file() {
+        assert_eq!(
+            eval_ok("builtins.readFile ./src/tests/fs_tests/hello.txt"),
+            Value::Str("Hello, world!\n".into())
+        );
+    }
+
+    #[test]
+    fn eval_relative_string() {
+        assert_eq!(
+            eval_ok(r#"builtins.readFile "src/tests/fs_tests/nested/file.txt""#),
+            Value::Str("nested\n".into())
+        );
+    }
+
+    #[test]
+    fn eval_missing_file() {
+        assert!(matches!(
+            eval_err("builtins.readFile ./src/tests/fs_tests/non_existent_file.txt"),
+            NixErrorKind::Io { path, .. } if path.ends_with("src/tests/fs_tests/non_existent_file.txt")
+        ));
+    }
+
+    #[test]
+    fn eval_invalid_type() {
+        assert_eq!(
+            eval_err("builtins.readFile 1"),
+            NixErrorKind::TypeMismatch {
+                expected: vec![NixTypeKind::String, NixTypeKind::Path],
+                got: NixTypeKind::Int
+            }
+        );
+    }
 }
 
 mod readFileType {
     use super::*;
+
+    #[test]
+    fn eval_file_types() {
+        assert_eq!(
+            eval_ok("builtins.readFileType ./src/tests/fs_tests/hello.txt"),
+            Value::Str("regular".into())
+        );
+        assert_eq!(
+            eval_ok("builtins.readFileType ./src/tests/fs_tests/nested"),
+            Value::Str("directory".into())
+        );
+    }
+
+    #[test]
+    fn eval_missing_path() {
+        assert!(matches!(
+            eval_err("builtins.readFileType ./src/tests/fs_tests/non_existent_file.txt"),
+            NixErrorKind::Io { .. }
+        ));
+    }
 }
 
 mod removeAttrs {
@@ -849,7 +1195,64 @@ mod traceVerbose {
 }
 
 mod tryEval {
+    use std::collections::BTreeMap;
+
     use super::*;
+
+    #[test]
+    fn eval_success() {
+        assert_eq!(
+            eval_ok("builtins.tryEval 1"),
+            Value::AttrSet(BTreeMap::from([
+                ("success".to_owned(), Value::Bool(true)),
+                ("value".to_owned(), Value::Int(1)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_failed_assertion() {
+        assert_eq!(
+            eval_ok("builtins.tryEval (assert false; 1)"),
+            Value::AttrSet(BTreeMap::from([
+                ("success".to_owned(), Value::Bool(false)),
+                ("value".to_owned(), Value::Bool(false)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_failed_import() {
+        assert_eq!(
+            eval_ok("builtins.tryEval (import ./non_existent_file.nix)"),
+            Value::AttrSet(BTreeMap::from([
+                ("success".to_owned(), Value::Bool(false)),
+                ("value".to_owned(), Value::Bool(false)),
+            ]))
+        );
+    }
+
+    #[test]
+    fn eval_lazy_failure() {
+        assert_eq!(
+            eval_ok("builtins.tryEval ({ a = assert false; 1; }.a)"),
+            Value::AttrSet(BTreeMap::from([
+                ("success".to_owned(), Value::Bool(false)),
+                ("value".to_owned(), Value::Bool(false)),
+            ]))
+        );
+        assert_eq!(eval_ok(r#"(x: 1) (abort "unused")"#), Value::Int(1));
+    }
+
+    #[test]
+    fn eval_abort() {
+        assert_eq!(
+            eval_err(r#"builtins.tryEval (abort "failed")"#),
+            NixErrorKind::Abort {
+                message: "failed".to_owned()
+            }
+        );
+    }
 }
 
 mod typeOf {
@@ -860,6 +1263,81 @@ mod unsafeDiscardOutputDependency {
     use super::*;
 }
 
+mod unsafeDiscardStringContext {
+    use super::*;
+
+    #[test]
+    fn eval_discards_context() {
+        let path = std::env::current_dir().unwrap().join("foo");
+        assert_eq!(
+            eval_ok(r#"builtins.unsafeDiscardStringContext "${./foo}""#),
+            Value::Str(path.display().to_string().into())
+        );
+    }
+}
+
 mod zipAttrsWith {
     use super::*;
 }
+
+// Constants
+
+mod currentSystem {
+    use super::*;
+    use crate::eval::execution::{default_system, Evaluator};
+
+    #[test]
+    fn eval_default_system() {
+        assert_eq!(
+            eval_ok("builtins.currentSystem"),
+            Value::Str(default_system().into())
+        );
+    }
+
+    #[test]
+    fn eval_overridden_system() {
+        let mut evaluator = Evaluator::new().unwrap();
+        evaluator.set_system("riscv64-linux");
+        let workdir = std::env::current_dir().unwrap();
+        assert_eq!(
+            evaluator
+                .evaluate("builtins.currentSystem", &workdir)
+                .unwrap(),
+            Value::Str("riscv64-linux".into())
+        );
+    }
+}
+
+mod currentTime {
+    use super::*;
+
+    #[test]
+    fn eval_current_time() {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let Value::Int(current_time) = eval_ok("builtins.currentTime") else {
+            panic!("builtins.currentTime should be an int");
+        };
+        assert!((now - current_time).abs() < 60);
+    }
+}
+
+mod langVersion {
+    use super::*;
+
+    #[test]
+    fn eval_lang_version() {
+        assert_eq!(eval_ok("builtins.langVersion"), Value::Int(6));
+    }
+}
+
+mod nixVersion {
+    use super::*;
+
+    #[test]
+    fn eval_nix_version() {
+        assert_eq!(eval_ok("builtins.nixVersion"), Value::Str("2.18.1".into()));
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use crate::eval::{
+    emit_js::emit_module_with_source_map,
+    error::NixError,
+    eval_cache::{cache_key, CachedModule, EvalCache},
+    execution::Evaluator,
+    source_map::SourceMap,
+    types::Value,
+};
+
+fn temp_cache_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rix-{name}-{}", std::process::id()))
+}
+
+#[test]
+fn cache_key_depends_on_source() {
+    assert_eq!(cache_key("1 + 2"), cache_key("1 + 2"));
+    assert_ne!(cache_key("1 + 2"), cache_key("1 + 3"));
+    assert_eq!(cache_key("1 + 2").len(), 64);
+}
+
+#[test]
+fn cache_get_missing() {
+    let eval_cache = EvalCache::new(temp_cache_dir("eval-cache-missing"));
+    assert_eq!(eval_cache.get("1 + 2"), None);
+}
+
+#[test]
+fn cache_set_and_get() {
+    let cache_dir = temp_cache_dir("eval-cache-set");
+    let eval_cache = EvalCache::new(cache_dir.clone());
+    let source_map = SourceMap::from_offsets("export default (ctx) => 3;", "1 + 2", &[(24, 0)]);
+    eval_cache.set_js_source("1 + 2", "export default (ctx) => 3;", &source_map);
+    assert_eq!(
+        eval_cache.get("1 + 2"),
+        Some(CachedModule {
+            js_source: "export default (ctx) => 3;".to_owned(),
+            source_map,
+            code_cache: None,
+        })
+    );
+
+    eval_cache.set_code_cache("1 + 2", &[1, 2, 3]);
+    assert_eq!(
+        eval_cache.get("1 + 2").and_then(|module| module.code_cache),
+        Some(vec![1, 2, 3])
+    );
+    std::fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[test]
+fn evaluator_with_eval_cache() -> Result<(), NixError> {
+    let cache_dir = temp_cache_dir("eval-cache-evaluator");
+    let workdir = std::env::current_dir().unwrap();
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_eval_cache(Some(EvalCache::new(cache_dir.clone())));
+
+    // The first evaluation fills the cache, the second one reads from it.
+    for _ in 0..2 {
+        assert_eq!(evaluator.evaluate("1 + 2", &workdir)?, Value::Int(3));
+    }
+    let cached_module = EvalCache::new(cache_dir.clone()).get("1 + 2").unwrap();
+    assert!(cached_module.js_source.contains("export default"));
+    assert!(cached_module.code_cache.is_some());
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn evaluator_rejects_invalid_code_cache() -> Result<(), NixError> {
+    let cache_dir = temp_cache_dir("eval-cache-invalid");
+    let workdir = std::env::current_dir().unwrap();
+    let eval_cache = EvalCache::new(cache_dir.clone());
+    let (js_source, source_map) = emit_module_with_source_map("1 + 2")?;
+    eval_cache.set_js_source("1 + 2", &js_source, &source_map);
+    eval_cache.set_code_cache("1 + 2", b"not a code cache");
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_eval_cache(Some(eval_cache.clone()));
+
+    assert_eq!(evaluator.evaluate("1 + 2", &workdir)?, Value::Int(3));
+    assert_ne!(
+        eval_cache.get("1 + 2").and_then(|module| module.code_cache),
+        Some(b"not a code cache".to_vec())
+    );
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn evaluator_replaces_corrupt_js_source() -> Result<(), NixError> {
+    let cache_dir = temp_cache_dir("eval-cache-corrupt");
+    let workdir = std::env::current_dir().unwrap();
+    let eval_cache = EvalCache::new(cache_dir.clone());
+    let (js_source, source_map) = emit_module_with_source_map("1 + 2")?;
+    let truncated_js_source = &js_source[..js_source.len() / 2];
+    eval_cache.set_js_source("1 + 2", truncated_js_source, &source_map);
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_eval_cache(Some(eval_cache.clone()));
+
+    assert_eq!(evaluator.evaluate("1 + 2", &workdir)?, Value::Int(3));
+    assert_eq!(
+        eval_cache.get("1 + 2").map(|module| module.js_source),
+        Some(js_source)
+    );
+    std::fs::remove_dir_all(cache_dir).unwrap();
+    Ok(())
+}
+
+#[test]
+fn cache_concurrent_writes() {
+    let cache_dir = temp_cache_dir("eval-cache-concurrent");
+    let source_map = SourceMap::from_offsets("export default (ctx) => 3;", "1 + 2", &[(24, 0)]);
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                let eval_cache = EvalCache::new(cache_dir.clone());
+                for _ in 0..20 {
+                    eval_cache.set_js_source("1 + 2", "export default (ctx) => 3;", &source_map);
+                }
+            });
+        }
+    });
+    assert_eq!(
+        EvalCache::new(cache_dir.clone())
+            .get("1 + 2")
+            .map(|module| module.js_source),
+        Some("export default (ctx) => 3;".to_owned())
+    );
+    let tmp_files = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .filter(|entry| {
+            let path = entry.as_ref().unwrap().path();
+            path.extension().is_some_and(|ext| ext == "tmp")
+        })
+        .count();
+    assert_eq!(tmp_files, 0);
+    std::fs::remove_dir_all(cache_dir).unwrap();
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::eval::{
+    error::{NixError, NixErrorKind},
+    execution::Evaluator,
+    fs::{FileType, InMemoryFs, NixFs},
+    types::Value,
+};
+
+const PROJECT_DIR: &str = "/project";
+
+/// The fixtures in `src/tests/import_tests` and `src/tests/fs_tests`, loaded
+/// from disk into memory at the same paths under [`PROJECT_DIR`].
+fn fixtures_fs() -> InMemoryFs {
+    let mut fs = InMemoryFs::new();
+    for fixtures_dir in ["src/tests/import_tests", "src/tests/fs_tests"] {
+        add_dir_from_disk(&mut fs, Path::new(fixtures_dir));
+    }
+    fs
+}
+
+fn add_dir_from_disk(fs: &mut InMemoryFs, dir: &Path) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            add_dir_from_disk(fs, &path);
+        } else {
+            fs.add_file(
+                Path::new(PROJECT_DIR).join(&path),
+                std::fs::read(&path).unwrap(),
+            );
+        }
+    }
+}
+
+fn eval_in_memory(nix_expr: &str) -> Result<Value, NixError> {
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_fs(fixtures_fs());
+    evaluator.evaluate(nix_expr, &Path::new(PROJECT_DIR).join("src/tests"))
+}
+
+#[test]
+fn in_memory_canonicalize() {
+    let fs = fixtures_fs();
+    assert_eq!(
+        fs.canonicalize(Path::new(
+            "/project/src/tests/import_tests/nested/../basic.nix"
+        ))
+        .unwrap(),
+        PathBuf::from("/project/src/tests/import_tests/basic.nix")
+    );
+    assert_eq!(
+        fs.canonicalize(Path::new("/project/src/tests/./import_tests/nested"))
+            .unwrap(),
+        PathBuf::from("/project/src/tests/import_tests/nested")
+    );
+    assert!(fs
+        .canonicalize(Path::new("/project/src/tests/missing.nix"))
+        .is_err());
+}
+
+#[test]
+fn in_memory_read_dir() {
+    let fs = fixtures_fs();
+    assert_eq!(
+        fs.read_dir(Path::new("/project/src/tests/fs_tests"))
+            .unwrap(),
+        vec![
+            ("__proto__".to_owned(), FileType::Regular),
+            ("hello.txt".to_owned(), FileType::Regular),
+            ("nested".to_owned(), FileType::Directory),
+        ]
+    );
+    assert!(fs
+        .read_dir(Path::new("/project/src/tests/fs_tests/hello.txt"))
+        .is_err());
+    assert!(fs.read_dir(Path::new("/missing")).is_err());
+}
+
+#[test]
+fn in_memory_file_type() {
+    let fs = fixtures_fs();
+    assert_eq!(
+        fs.file_type(Path::new("/project/src/tests/fs_tests/hello.txt"))
+            .unwrap(),
+        FileType::Regular
+    );
+    assert_eq!(
+        fs.file_type(Path::new("/project/src/tests/fs_tests/nested"))
+            .unwrap(),
+        FileType::Directory
+    );
+    assert!(fs
+        .file_type(Path::new("/project/src/tests/missing.nix"))
+        .is_err());
+}
+
+#[test]
+fn eval_in_memory_import() -> Result<(), NixError> {
+    assert_eq!(
+        eval_in_memory("(builtins.import ./import_tests/basic.nix).data")?,
+        Value::Str("imported!".into())
+    );
+    assert_eq!(
+        eval_in_memory("(builtins.import ./import_tests/child-folder-import.nix).data")?,
+        Value::Str("imported!".into())
+    );
+    assert_eq!(
+        eval_in_memory("(builtins.import ./import_tests/nested/parent-folder-import.nix).data")?,
+        Value::Str("imported!".into())
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_in_memory_missing_import() {
+    assert!(matches!(
+        eval_in_memory("builtins.import ./missing.nix").map_err(|err| err.kind),
+        Err(NixErrorKind::ImportFailed { path, .. }) if path == "/project/src/tests/missing.nix"
+    ));
+}
+
+#[test]
+fn eval_in_memory_read_file() -> Result<(), NixError> {
+    assert_eq!(
+        eval_in_memory("builtins.readFile ./fs_tests/hello.txt")?,
+        Value::Str("Hello, world!\n".into())
+    );
+    assert_eq!(
+        eval_in_memory("builtins.pathExists ./fs_tests/missing.txt")?,
+        Value::Bool(false)
+    );
+    assert_eq!(
+        eval_in_memory("builtins.readDir ./fs_tests")?,
+        Value::AttrSet(BTreeMap::from([
+            ("__proto__".to_owned(), Value::Str("regular".into())),
+            ("hello.txt".to_owned(), Value::Str("regular".into())),
+            ("nested".to_owned(), Value::Str("directory".into())),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn in_memory_nix_source() -> Result<(), NixError> {
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_fs(fixtures_fs());
+    evaluator.evaluate(
+        "builtins.import ./src/tests/import_tests/basic.nix",
+        Path::new(PROJECT_DIR),
+    )?;
+    let basic_nix = std::fs::read_to_string("src/tests/import_tests/basic.nix").unwrap();
+    assert_eq!(
+        evaluator.nix_source("/project/src/tests/import_tests/basic.nix"),
+        Some(basic_nix)
+    );
+    assert_eq!(
+        evaluator.nix_source("/project/src/tests/fs_tests/hello.txt"),
+        Some("Hello, world!\n".to_owned())
+    );
+    assert_eq!(evaluator.nix_source("/project/missing.nix"), None);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use crate::eval::hash::{Hash, HashAlgo, HashFormat};
+
+fn hash_string(algo: HashAlgo, data: &str, format: HashFormat) -> String {
+    Hash::of(algo, data.as_bytes()).to_string(format)
+}
+
+#[test]
+fn nix32_golden_values() {
+    assert_eq!(
+        hash_string(HashAlgo::Sha256, "hello", HashFormat::Nix32),
+        "094qif9n4cq4fdg459qzbhg1c6wywawwaaivx0k0x8xhbyx4vwic"
+    );
+    assert_eq!(
+        hash_string(HashAlgo::Sha256, "", HashFormat::Nix32),
+        "0mdqa9w1p6cmli6976v4wi0sw9r4p5prkj7lzfd1877wk11c9c73"
+    );
+    assert_eq!(
+        hash_string(HashAlgo::Md5, "hello", HashFormat::Nix32),
+        "4jqlbi14cxf6wpcajbphm40hax"
+    );
+    assert_eq!(
+        hash_string(HashAlgo::Sha1, "hello", HashFormat::Nix32),
+        "9m1skbnr5i43n3yypvda5s65vhfwdx5a"
+    );
+}
+
+#[test]
+fn base64_golden_values() {
+    assert_eq!(
+        hash_string(HashAlgo::Sha256, "hello", HashFormat::Base64),
+        "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ="
+    );
+    assert_eq!(
+        hash_string(HashAlgo::Sha512, "hello", HashFormat::Base64),
+        "m3HSJL1i83hdltRq0+o9czGb+8KJDKra4t/3JRlnPKcjI8PZm6XBHXx6zG4UuMXaDEZjR1wuXDre9G9zvN7AQw=="
+    );
+    assert_eq!(
+        hash_string(HashAlgo::Sha256, "", HashFormat::Sri),
+        "sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU="
+    );
+}
+
+#[test]
+fn parse_round_trip() {
+    for algo in [
+        HashAlgo::Md5,
+        HashAlgo::Sha1,
+        HashAlgo::Sha256,
+        HashAlgo::Sha512,
+    ] {
+        let hash = Hash::of(algo, b"hello");
+        for format in [HashFormat::Base16, HashFormat::Nix32, HashFormat::Base64] {
+            assert_eq!(
+                Hash::parse(&hash.to_string(format), Some(algo)),
+                Ok(hash.clone())
+            );
+        }
+        assert_eq!(
+            Hash::parse(&hash.to_string(HashFormat::Sri), None),
+            Ok(hash.clone())
+        );
+        let prefixed = format!("{}:{}", algo.name(), hash.to_string(HashFormat::Nix32));
+        assert_eq!(Hash::parse(&prefixed, None), Ok(hash));
+    }
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        Hash::parse("5d41402abc4b2a76b9719d911017c592", None),
+        Err("hash '5d41402abc4b2a76b9719d911017c592' does not include a type, nor is the type otherwise known from context".to_owned())
+    );
+    assert_eq!(
+        Hash::parse(
+            "md5:5d41402abc4b2a76b9719d911017c592",
+            Some(HashAlgo::Sha256)
+        ),
+        Err("hash 'md5:5d41402abc4b2a76b9719d911017c592' should have type 'sha256'".to_owned())
+    );
+    assert_eq!(
+        Hash::parse("5d41402a", Some(HashAlgo::Md5)),
+        Err("hash '5d41402a' has wrong length for hash type 'md5'".to_owned())
+    );
+    assert_eq!(
+        Hash::parse("md5:5d41402abc4b2a76b9719d911017c59g", None),
+        Err(
+            "hash 'md5:5d41402abc4b2a76b9719d911017c59g' has wrong length for hash type 'md5'"
+                .to_owned()
+        )
+    );
+    assert_eq!(
+        HashAlgo::parse("sha3"),
+        Err("unknown hash algorithm 'sha3'".to_owned())
+    );
+    assert_eq!(
+        HashFormat::parse("base58"),
+        Err("unknown hash format 'base58'".to_owned())
+    );
+}
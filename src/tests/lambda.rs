@@ -1,5 +1,8 @@
 use crate::{
-    eval::{error::NixErrorKind, types::Value},
+    eval::{
+        error::NixErrorKind,
+        types::{NixTypeKind, Value},
+    },
     tests::{eval_err, eval_ok},
 };
 
@@ -8,6 +11,13 @@ fn eval_lambda() {
     assert_eq!(eval_ok("a: 1"), Value::Lambda);
 }
 
+#[test]
+fn eval_primop() {
+    assert_eq!(eval_ok("builtins.add"), Value::PrimOp("add".to_owned()));
+    assert_eq!(eval_ok("abort"), Value::PrimOp("abort".to_owned()));
+    assert_eq!(eval_ok("builtins.add 1"), Value::Lambda);
+}
+
 #[test]
 fn eval_lambda_application() {
     assert_eq!(eval_ok("(a: 1) 2"), Value::Int(1));
@@ -18,11 +28,13 @@ fn eval_lambda_application() {
 fn eval_pattern_lambda() {
     assert_eq!(eval_ok("({a, b}: a + b) {a = 1; b = 2;}"), Value::Int(3));
     assert_eq!(eval_ok("({a, b ? 2}: a + b) {a = 1;}"), Value::Int(3));
-    // TODO: Improve error
     assert_eq!(
         eval_err("{a, a}: a"),
-        NixErrorKind::UnexpectedRustError {
-            message: "duplicate formal function argument 'a'.".to_string()
+        NixErrorKind::ParseError {
+            file: "«string»".to_owned(),
+            line: 1,
+            column: 5,
+            message: "duplicate formal function argument 'a'".to_string()
         }
     );
 }
@@ -30,11 +42,13 @@ fn eval_pattern_lambda() {
 #[test]
 fn eval_pattern_lambda_args_binding() {
     assert_eq!(eval_ok("({a}@args: args.a) {a = 1;}"), Value::Int(1));
-    // TODO: Improve error
     assert_eq!(
         eval_err("{a}@a: a"),
-        NixErrorKind::UnexpectedRustError {
-            message: "duplicate formal function argument 'a'.".to_string()
+        NixErrorKind::ParseError {
+            file: "«string»".to_owned(),
+            line: 1,
+            column: 2,
+            message: "duplicate formal function argument 'a'".to_string()
         }
     );
     assert_eq!(
@@ -44,3 +58,18 @@ fn eval_pattern_lambda_args_binding() {
         }
     );
 }
+
+#[test]
+fn eval_pattern_lambda_non_attrset_arg() {
+    assert_eq!(
+        eval_ok("({a}: a) (let x = { a = 1; }; in x)"),
+        Value::Int(1)
+    );
+    assert_eq!(
+        eval_err("({a}: a) 1"),
+        NixErrorKind::TypeMismatch {
+            expected: vec![NixTypeKind::Set],
+            got: NixTypeKind::Int
+        }
+    );
+}
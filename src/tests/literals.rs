@@ -1,7 +1,7 @@
 use crate::{
     eval::{
         error::NixErrorKind,
-        types::{NixTypeKind, Value},
+        types::{NixString, NixTypeKind, Value},
     },
     tests::{eval_err, eval_ok},
 };
@@ -24,6 +24,16 @@ fn eval_int_literals() {
     );
 }
 
+#[test]
+fn eval_null_literal() {
+    assert_eq!(eval_ok("null"), Value::Null);
+    assert_eq!(
+        eval_ok("{ a = null; }"),
+        Value::AttrSet([("a".to_owned(), Value::Null)].into())
+    );
+    assert_eq!(eval_ok("[ null ]"), Value::List(vec![Value::Null]));
+}
+
 #[test]
 fn eval_float_literals() {
     assert_eq!(eval_ok("1.0"), Value::Float(1.0));
@@ -53,23 +63,23 @@ fn eval_bool_literals() {
 
 #[test]
 fn eval_string_literal() {
-    assert_eq!(eval_ok(r#""Hello!""#), Value::Str("Hello!".to_owned()));
+    assert_eq!(eval_ok(r#""Hello!""#), Value::Str("Hello!".into()));
 }
 
 #[test]
 fn eval_string_literal_escape_codes() {
     assert_eq!(
         eval_ok(r#""\"\$\n\r\t\\`""#),
-        Value::Str("\"$\n\r\t\\`".to_owned())
+        Value::Str("\"$\n\r\t\\`".into())
     );
-    assert_eq!(eval_ok("\"a \n b\""), Value::Str("a \n b".to_owned()));
+    assert_eq!(eval_ok("\"a \n b\""), Value::Str("a \n b".into()));
 }
 
 #[test]
 fn eval_string_uri() {
     assert_eq!(
         eval_ok("http://foo.bat/moo"),
-        Value::Str("http://foo.bat/moo".to_owned())
+        Value::Str("http://foo.bat/moo".into())
     );
 }
 
@@ -81,7 +91,7 @@ fn eval_indented_string() {
   Hello
   World!''"
         ),
-        Value::Str("Hello\nWorld!".to_owned())
+        Value::Str("Hello\nWorld!".into())
     );
     assert_eq!(
         eval_ok(
@@ -90,11 +100,11 @@ fn eval_indented_string() {
  b
    c''"
         ),
-        Value::Str(" a\nb\n  c".to_owned())
+        Value::Str(" a\nb\n  c".into())
     );
     assert_eq!(
         eval_ok("''''$'''$${}''\\n''\\t''\\r''\\\\''"),
-        Value::Str("$''$${}\n\t\r\\".to_owned())
+        Value::Str("$''$${}\n\t\r\\".into())
     );
 }
 
@@ -102,10 +112,13 @@ fn eval_indented_string() {
 fn eval_string_interpolation() {
     let path = std::env::current_dir().unwrap();
 
-    assert_eq!(eval_ok(r#""${"A"}""#), Value::Str("A".to_owned()));
+    assert_eq!(eval_ok(r#""${"A"}""#), Value::Str("A".into()));
     assert_eq!(
         eval_ok(r#""${./foo}""#),
-        Value::Str(format!("{}/foo", path.display()))
+        Value::Str(NixString {
+            value: format!("{}/foo", path.display()),
+            context: [format!("{}/foo", path.display())].into(),
+        })
     );
     assert_eq!(
         eval_err(r#""${1}""#),
@@ -138,7 +151,7 @@ fn eval_list_literal() {
         Value::List(vec![
             Value::Int(42),
             Value::Bool(true),
-            Value::Str("answer".to_owned())
+            Value::Str("answer".into())
         ])
     );
     assert_eq!(
@@ -152,7 +165,7 @@ fn eval_list_literal() {
         Value::List(vec![
             Value::Int(42),
             Value::Bool(true),
-            Value::Str("answer".to_owned())
+            Value::Str("answer".into())
         ])
     );
 }
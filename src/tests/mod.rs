@@ -3,32 +3,142 @@
 #![allow(clippy::approx_constant)]
 
 use crate::eval::{
-    error::NixErrorKind,
-    execution::evaluate,
-    types::{NixTypeKind, Value},
+    error::{NixError, NixErrorKind},
+    execution::{evaluate_attr_path, Evaluator},
+    types::{EvalResult, NixTypeKind, Value},
 };
 
 mod attr_set;
 mod builtins;
+mod eval_cache;
+mod fs;
+mod hash;
 mod lambda;
 mod literals;
 mod operators;
+mod print;
+mod restrictions;
+mod source_map;
+mod trace;
 
-fn eval_ok(nix_expr: &str) -> Value {
+fn evaluate(nix_expr: &str) -> EvalResult {
     let workdir = std::env::current_dir().unwrap();
-    match evaluate(nix_expr, &workdir) {
+    Evaluator::new()?.evaluate(nix_expr, &workdir)
+}
+
+fn eval_ok(nix_expr: &str) -> Value {
+    match evaluate(nix_expr) {
         Ok(val) => val,
         Err(err) => panic!("eval '{nix_expr}' shouldn't fail.\nError message: {err:?}",),
     }
 }
 
 fn eval_err(nix_expr: &str) -> NixErrorKind {
-    let workdir = std::env::current_dir().unwrap();
-    evaluate(nix_expr, &workdir)
+    evaluate(nix_expr)
         .expect_err(&format!("eval '{nix_expr}' expected an error"))
         .kind
 }
 
+#[test]
+fn eval_reuses_evaluator() -> Result<(), NixError> {
+    let workdir = std::env::current_dir().unwrap();
+    let mut evaluator = Evaluator::new()?;
+    for int in 0..100 {
+        assert_eq!(
+            evaluator.evaluate(&format!("{int} + 1"), &workdir)?,
+            Value::Int(int + 1)
+        );
+    }
+    assert!(evaluator.evaluate("abort \"failed\"", &workdir).is_err());
+    assert_eq!(
+        evaluator.evaluate("let a = 1; in a", &workdir)?,
+        Value::Int(1)
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_assert() {
+    assert_eq!(eval_ok("assert true; 1"), Value::Int(1));
+    assert_eq!(eval_ok("let a = 1; in assert a == 1; a + 1"), Value::Int(2));
+}
+
+#[test]
+fn eval_assert_failed() {
+    assert_eq!(
+        eval_err("assert 1 == 2; 1"),
+        NixErrorKind::AssertionFailed {
+            condition: "1 == 2".to_owned()
+        }
+    );
+    assert_eq!(
+        eval_err("assert 1; 1"),
+        NixErrorKind::TypeMismatch {
+            expected: vec![NixTypeKind::Bool],
+            got: NixTypeKind::Int,
+        }
+    );
+}
+
+#[test]
+fn eval_parse_error() {
+    assert_eq!(
+        eval_err("1 +"),
+        NixErrorKind::ParseError {
+            file: "«string»".to_owned(),
+            line: 1,
+            column: 4,
+            message: "syntax error, unexpected end of file".to_owned()
+        }
+    );
+    assert_eq!(
+        eval_err("{\n  a = 1\n}"),
+        NixErrorKind::ParseError {
+            file: "«string»".to_owned(),
+            line: 3,
+            column: 1,
+            message: "syntax error, unexpected '}', expecting ';'".to_owned()
+        }
+    );
+}
+
+#[test]
+fn eval_attr_path() {
+    let workdir = std::env::current_dir().unwrap();
+    let attr_path = ["a".to_owned(), "b.c".to_owned()];
+    assert_eq!(
+        evaluate_attr_path(
+            "{ a = { \"b.c\" = 1; d = abort \"d\"; }; e = abort \"e\"; }",
+            &workdir,
+            &attr_path
+        )
+        .unwrap(),
+        Value::Int(1)
+    );
+    assert_eq!(
+        evaluate_attr_path("{ a = { }; }", &workdir, &attr_path)
+            .unwrap_err()
+            .kind,
+        NixErrorKind::MissingAttribute {
+            attr_path: attr_path.to_vec()
+        }
+    );
+}
+
+#[test]
+fn eval_attr_path_auto_call() {
+    let workdir = std::env::current_dir().unwrap();
+    assert_eq!(
+        evaluate_attr_path("{ x ? 1 }: { a = x; }", &workdir, &["a".to_owned()]).unwrap(),
+        Value::Int(1)
+    );
+    assert_eq!(
+        evaluate_attr_path("x: x", &workdir, &[]).unwrap(),
+        Value::Lambda
+    );
+    assert_eq!(eval_ok("{ x ? 1 }: x"), Value::Lambda);
+}
+
 #[test]
 fn eval_if_then_else() {
     assert_eq!(eval_ok("if true then 1 else 0"), Value::Int(1));
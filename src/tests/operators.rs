@@ -1,7 +1,7 @@
 use crate::{
     eval::{
         error::NixErrorKind,
-        types::{NixTypeKind, Value},
+        types::{NixString, NixTypeKind, Value},
     },
     tests::{eval_err, eval_ok},
 };
@@ -38,11 +38,11 @@ fn eval_mixed_arithmetic() {
 fn eval_string_concatenation() {
     assert_eq!(
         eval_ok("\"hello\" + \"world\""),
-        Value::Str("helloworld".to_string())
+        Value::Str("helloworld".into())
     );
     assert_eq!(
         eval_ok("\"hello\" + \" \" + \"world\""),
-        Value::Str("hello world".to_string())
+        Value::Str("hello world".into())
     );
 }
 
@@ -133,7 +133,10 @@ fn eval_path_string_concatenation() {
     );
     assert_eq!(
         eval_ok("\"hello\" + ./world"),
-        Value::Str(format!("hello{}/world", curr_dir.display()))
+        Value::Str(NixString {
+            value: format!("hello{}/world", curr_dir.display()),
+            context: [format!("{}/world", curr_dir.display())].into(),
+        })
     );
 }
 
@@ -256,11 +259,11 @@ fn eval_list_operations() {
     assert_eq!(
         eval_ok(r#"["a"] ++ [1] ++ [[] [] "b"]"#),
         Value::List(vec![
-            Value::Str("a".to_owned()),
+            Value::Str("a".into()),
             Value::Int(1),
             Value::List(vec![]),
             Value::List(vec![]),
-            Value::Str("b".to_owned())
+            Value::Str("b".into())
         ])
     );
 }
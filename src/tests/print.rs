@@ -0,0 +1,61 @@
+use super::*;
+
+fn print(nix_expr: &str) -> String {
+    eval_ok(nix_expr).to_string()
+}
+
+fn print_pretty(nix_expr: &str) -> String {
+    format!("{:#}", eval_ok(nix_expr))
+}
+
+#[test]
+fn print_literals() {
+    assert_eq!(print("true"), "true");
+    assert_eq!(print("-3"), "-3");
+    assert_eq!(print("null"), "null");
+    assert_eq!(print("x: x"), "<LAMBDA>");
+    assert_eq!(print("builtins.add"), "<PRIMOP>");
+    assert_eq!(print("/foo/bar"), "/foo/bar");
+}
+
+#[test]
+fn print_floats() {
+    assert_eq!(Value::Float(12.5).to_string(), "12.5");
+    assert_eq!(Value::Float(1.0).to_string(), "1");
+    assert_eq!(Value::Float(1.0 / 3.0).to_string(), "0.333333");
+    assert_eq!(Value::Float(1234567.0).to_string(), "1.23457e+06");
+    assert_eq!(Value::Float(0.00001234).to_string(), "1.234e-05");
+    assert_eq!(Value::Float(-0.0).to_string(), "-0");
+}
+
+#[test]
+fn print_string_escaping() {
+    assert_eq!(print(r#""a\"b""#), r#""a\"b""#);
+    assert_eq!(print(r#""a\nb\tc\\""#), r#""a\nb\tc\\""#);
+    assert_eq!(print(r#""\${x} $y""#), r#""\${x} $y""#);
+}
+
+#[test]
+fn print_attrset() {
+    assert_eq!(print("{ }"), "{ }");
+    assert_eq!(
+        print(r#"{ z = 1; a = [ 1 "x" ]; "if" = 2; "a b" = 3; b-c' = 4; }"#),
+        r#"{ "a b" = 3; a = [ 1 "x" ]; b-c' = 4; "if" = 2; z = 1; }"#
+    );
+}
+
+#[test]
+fn print_list() {
+    assert_eq!(print("[ ]"), "[ ]");
+    assert_eq!(print("[ 1 [ 2 ] { a = 3; } ]"), "[ 1 [ 2 ] { a = 3; } ]");
+}
+
+#[test]
+fn print_pretty_nested() {
+    assert_eq!(print_pretty("{ a = 1; }"), "{ a = 1; }");
+    assert_eq!(print_pretty("[ [ ] ]"), "[\n  [ ]\n]");
+    assert_eq!(
+        print_pretty("{ b = [ 1 2 ]; a = { c = { }; }; }"),
+        "{\n  a = {\n    c = { };\n  };\n  b = [\n    1\n    2\n  ];\n}"
+    );
+}
@@ -0,0 +1,173 @@
+use std::path::{Path, PathBuf};
+
+use crate::eval::{
+    error::{NixError, NixErrorKind},
+    execution::Evaluator,
+    fs::{InMemoryFs, OsFs},
+    restrictions::{EvalMode, EvalRestrictions},
+    types::Value,
+};
+
+fn eval_restricted(
+    mode: EvalMode,
+    allowed_paths: &[&str],
+    nix_expr: &str,
+) -> Result<Value, NixError> {
+    let workdir = std::env::current_dir().unwrap();
+    let mut evaluator = Evaluator::new()?;
+    evaluator.set_restrictions(EvalRestrictions {
+        mode,
+        allowed_paths: allowed_paths
+            .iter()
+            .map(|path| workdir.join(path))
+            .collect(),
+    });
+    evaluator.evaluate(nix_expr, &workdir)
+}
+
+fn forbidden_path(nix_path: &str) -> NixErrorKind {
+    NixErrorKind::AccessForbidden {
+        path: std::env::current_dir()
+            .unwrap()
+            .join(nix_path)
+            .to_string_lossy()
+            .into_owned(),
+    }
+}
+
+#[test]
+fn is_path_allowed() {
+    let mut fs = InMemoryFs::new();
+    fs.add_file("/allowed/file.nix", "1");
+    fs.add_file("/other/file.nix", "2");
+    let restrictions = EvalRestrictions {
+        mode: EvalMode::Restricted,
+        allowed_paths: vec![PathBuf::from("/allowed")],
+    };
+    assert!(restrictions.is_path_allowed(&fs, Path::new("/allowed/file.nix")));
+    assert!(restrictions.is_path_allowed(&fs, Path::new("/allowed/missing.nix")));
+    assert!(!restrictions.is_path_allowed(&fs, Path::new("/other/file.nix")));
+    assert!(!restrictions.is_path_allowed(&fs, Path::new("/allowed/../other/file.nix")));
+    assert!(!restrictions.is_path_allowed(&fs, Path::new("/allowed-not/file.nix")));
+}
+
+#[cfg(unix)]
+#[test]
+fn is_path_allowed_resolves_missing_paths_through_symlinks() {
+    let dir = std::env::temp_dir().join(format!("rix-restrictions-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("allowed")).unwrap();
+    std::fs::create_dir_all(dir.join("other")).unwrap();
+    std::os::unix::fs::symlink(dir.join("other"), dir.join("allowed/link")).unwrap();
+    let restrictions = EvalRestrictions {
+        mode: EvalMode::Restricted,
+        allowed_paths: vec![dir.join("allowed")],
+    };
+    assert!(restrictions.is_path_allowed(&OsFs, &dir.join("allowed/missing/file.nix")));
+    assert!(!restrictions.is_path_allowed(&OsFs, &dir.join("allowed/link/missing.nix")));
+    assert!(!restrictions.is_path_allowed(&OsFs, &dir.join("allowed/missing/../../other")));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn impure_allows_any_path() {
+    let restrictions = EvalRestrictions::default();
+    assert!(restrictions.is_path_allowed(&OsFs, Path::new("/etc/passwd")));
+}
+
+#[test]
+fn eval_restricted_allowed_import() -> Result<(), NixError> {
+    assert_eq!(
+        eval_restricted(
+            EvalMode::Restricted,
+            &["src/tests/import_tests"],
+            "(builtins.import ./src/tests/import_tests/child-folder-import.nix).dataPath",
+        )?,
+        Value::Str("imported!".into())
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_restricted_forbidden_import() {
+    assert_eq!(
+        eval_restricted(
+            EvalMode::Restricted,
+            &["src/tests/fs_tests"],
+            "builtins.import ./src/tests/import_tests/basic.nix",
+        )
+        .map_err(|err| err.kind),
+        Err(forbidden_path("src/tests/import_tests/basic.nix"))
+    );
+}
+
+#[test]
+fn eval_restricted_forbidden_read() {
+    assert_eq!(
+        eval_restricted(
+            EvalMode::Restricted,
+            &["src/tests/import_tests"],
+            r#"builtins.readFile "src/tests/import_tests/../fs_tests/hello.txt""#,
+        )
+        .map_err(|err| err.kind),
+        Err(forbidden_path(
+            "src/tests/import_tests/../fs_tests/hello.txt"
+        ))
+    );
+    assert_eq!(
+        eval_restricted(
+            EvalMode::Restricted,
+            &[],
+            "builtins.pathExists ./src/tests/fs_tests/hello.txt",
+        )
+        .map_err(|err| err.kind),
+        Err(forbidden_path("src/tests/fs_tests/hello.txt"))
+    );
+}
+
+#[test]
+fn eval_pure_forbidden_read() {
+    assert_eq!(
+        eval_restricted(EvalMode::Pure, &[], "builtins.readDir ./src/tests/fs_tests",)
+            .map_err(|err| err.kind),
+        Err(forbidden_path("src/tests/fs_tests"))
+    );
+}
+
+#[test]
+fn eval_impure_read() -> Result<(), NixError> {
+    assert_eq!(
+        eval_restricted(
+            EvalMode::Impure,
+            &[],
+            "builtins.readFile ./src/tests/fs_tests/hello.txt",
+        )?,
+        Value::Str("Hello, world!\n".into())
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_pure_hides_environment() -> Result<(), NixError> {
+    assert_eq!(
+        eval_restricted(EvalMode::Pure, &[], r#"builtins.getEnv "PATH""#)?,
+        Value::Str("".into())
+    );
+    assert_eq!(
+        eval_restricted(EvalMode::Pure, &[], "builtins ? currentTime")?,
+        Value::Bool(false)
+    );
+    Ok(())
+}
+
+#[test]
+fn eval_restricted_hides_environment() -> Result<(), NixError> {
+    assert_eq!(
+        eval_restricted(EvalMode::Restricted, &[], r#"builtins.getEnv "PATH""#)?,
+        Value::Str("".into())
+    );
+    assert_eq!(
+        eval_restricted(EvalMode::Restricted, &[], "builtins ? currentTime")?,
+        Value::Bool(true)
+    );
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::eval::{
+    emit_js::emit_module_with_source_map,
+    source_map::{decode_vlq, encode_vlq, position_to_offset, SourceMap},
+};
+
+fn vlq(value: i64) -> String {
+    let mut out = String::new();
+    encode_vlq(value, &mut out);
+    out
+}
+
+#[test]
+fn source_map_encode_vlq() {
+    assert_eq!(vlq(0), "A");
+    assert_eq!(vlq(1), "C");
+    assert_eq!(vlq(-1), "D");
+    assert_eq!(vlq(15), "e");
+    assert_eq!(vlq(16), "gB");
+    assert_eq!(vlq(-17), "jB");
+    assert_eq!(vlq(1000), "w+B");
+}
+
+#[test]
+fn source_map_decode_vlq() {
+    for value in [0, 1, -1, 15, 16, -17, 1000] {
+        assert_eq!(decode_vlq(&mut vlq(value).chars()), Some(value));
+    }
+    assert_eq!(decode_vlq(&mut "g".chars()), None);
+    assert_eq!(decode_vlq(&mut "!".chars()), None);
+}
+
+#[test]
+fn source_map_decode_mappings() {
+    let source_map = SourceMap::from_offsets("a\nbc", "x\ny\nz", &[(3, 4), (0, 0), (2, 2)]);
+    assert_eq!(
+        SourceMap::decode_mappings(&source_map.encode_mappings()),
+        Some(source_map)
+    );
+    assert_eq!(SourceMap::decode_mappings("AA"), None);
+}
+
+#[test]
+fn source_map_position_to_offset() {
+    assert_eq!(position_to_offset("ab\n€c", 0, 1), Some(1));
+    assert_eq!(position_to_offset("ab\n€c", 1, 1), Some(6));
+    assert_eq!(position_to_offset("ab\n€c", 0, 5), Some(2));
+    assert_eq!(position_to_offset("ab\n€c", 2, 0), None);
+}
+
+#[test]
+fn source_map_from_offsets() {
+    let source_map = SourceMap::from_offsets("a\nbc", "x\ny\nz", &[(3, 4), (0, 0), (2, 2)]);
+    assert_eq!(source_map.encode_mappings(), "AAAA;AACA,CACA");
+    assert_eq!(source_map.original_position(1, 0), Some((1, 0)));
+    assert_eq!(source_map.original_position(1, 5), Some((2, 0)));
+    assert_eq!(source_map.original_position(2, 0), None);
+}
+
+#[test]
+fn source_map_utf16_columns() {
+    let source_map = SourceMap::from_offsets("'€'+1", "\"€\"+1", &[(6, 6)]);
+    assert_eq!(source_map.original_position(0, 4), Some((0, 4)));
+}
+
+#[test]
+fn source_map_points_at_nix_expressions() {
+    let nix_expr = "let x = 1;\nin x + 2";
+    let (js_source, source_map) = emit_module_with_source_map(nix_expr).unwrap();
+    let (js_line, js_line_src) = js_source
+        .lines()
+        .enumerate()
+        .find(|(_, line)| line.contains("ctx.lookup"))
+        .unwrap();
+    let lookup_column = js_line_src.find("ctx.lookup").unwrap() as u32;
+    assert_eq!(
+        source_map.original_position(js_line as u32, lookup_column),
+        Some((1, 3))
+    );
+}
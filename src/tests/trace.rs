@@ -0,0 +1,53 @@
+use crate::eval::error::NixStackFrame;
+
+use super::evaluate;
+
+fn frame(file: &str, line: usize, column: usize, description: &str) -> NixStackFrame {
+    NixStackFrame {
+        file: file.to_owned(),
+        line,
+        column,
+        description: description.to_owned(),
+    }
+}
+
+#[test]
+fn trace_points_at_nix_expressions() {
+    let err = evaluate("let\n  f = x: assert x; x;\nin f false").unwrap_err();
+    assert_eq!(
+        err.trace,
+        vec![
+            frame("«string»", 1, 1, "while evaluating an expression"),
+            frame("«string»", 3, 4, "from call site"),
+            frame("«string»", 2, 10, "while evaluating the assertion"),
+        ]
+    );
+}
+
+#[test]
+fn trace_includes_imported_files() {
+    let err =
+        evaluate("builtins.import ./src/tests/import_tests/failed-assertion.nix").unwrap_err();
+    let imported_file = std::env::current_dir()
+        .unwrap()
+        .join("src/tests/import_tests/failed-assertion.nix");
+    assert_eq!(
+        err.trace.last(),
+        Some(&frame(
+            imported_file.to_str().unwrap(),
+            1,
+            1,
+            "while evaluating the assertion"
+        ))
+    );
+}
+
+#[test]
+fn trace_of_runtime_error() {
+    let err = evaluate("{ a = 1; }.b").unwrap_err();
+    assert!(!err.trace.is_empty());
+    assert!(err
+        .trace
+        .iter()
+        .all(|frame| frame.file == "«string»" && frame.line == 1));
+}
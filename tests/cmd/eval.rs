@@ -25,8 +25,400 @@ fn eval_float_arithmetic_expr() {
         .stderr(predicate::str::is_empty());
 }
 
+#[test]
+fn eval_parse_error() {
+    assert_cmd(&["--expr", "{ a = 1 }"])
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "syntax error, unexpected '}', expecting ';'",
+        ))
+        .stderr(predicate::str::contains("at «string»:1:9:"))
+        .stderr(predicate::str::contains("1| { a = 1 }\n"))
+        .stderr(predicate::str::contains(" |         ^\n"));
+}
+
+#[test]
+fn eval_import_parse_error() {
+    assert_cmd(&[
+        "--expr",
+        "import ./src/tests/import_tests/invalid-syntax.nix",
+    ])
+    .code(3)
+    .stderr(predicate::str::contains(
+        "syntax error, unexpected '}', expecting ';'",
+    ))
+    .stderr(predicate::str::contains(
+        "src/tests/import_tests/invalid-syntax.nix:1:9:",
+    ))
+    .stderr(predicate::str::contains("1| { a = 1 }\n"));
+}
+
+#[test]
+fn eval_expr_attr_path() {
+    assert_cmd(&["--expr", "{ a.\"b.c\" = 1; d = abort \"d\"; }", "a.\"b.c\""])
+        .success()
+        .stdout(predicate::str::diff("1\n"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn eval_file() {
+    assert_cmd(&[
+        "-f",
+        "src/tests/import_tests/same-folder-import.nix",
+        "dataPath",
+    ])
+    .success()
+    .stdout(predicate::str::diff("\"imported!\"\n"))
+    .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn eval_file_function() {
+    assert_cmd(&[
+        "-f",
+        "src/tests/import_tests/function-with-defaults.nix",
+        "a",
+    ])
+    .success()
+    .stdout(predicate::str::diff("1\n"))
+    .stderr(predicate::str::is_empty());
+    assert_cmd(&["-f", "src/tests/import_tests/function-with-defaults.nix"])
+        .success()
+        .stdout(predicate::str::diff("{ a = 1; }\n"));
+}
+
+#[test]
+fn eval_empty_attr_name() {
+    for attr_path in ["a..b", ".a", "a."] {
+        assert_cmd(&["--expr", "{ a.b = 1; }", attr_path])
+            .code(2)
+            .stderr(predicate::str::contains(format!(
+                "empty attribute name in selection path '{attr_path}'"
+            )));
+    }
+}
+
+#[test]
+fn eval_file_missing_attr() {
+    assert_cmd(&["--file", "src/tests/import_tests/basic.nix", "missing"])
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("missing"));
+}
+
+#[test]
+fn eval_json() {
+    assert_cmd(&[
+        "--json",
+        "--expr",
+        "{ b = [ 1 2.5 \"x\" null ]; a = { c = true; }; p = /foo/bar; }",
+    ])
+    .success()
+    .stdout(predicate::str::diff(
+        "{\"a\":{\"c\":true},\"b\":[1,2.5,\"x\",null],\"p\":\"/foo/bar\"}\n",
+    ))
+    .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn eval_json_lambda() {
+    assert_cmd(&["--json", "--expr", "{ f = x: x; }"])
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "cannot convert a function to JSON",
+        ));
+}
+
+#[test]
+fn eval_raw() {
+    assert_cmd(&["--raw", "--expr", "\"a \\\"quoted\\\" string\""])
+        .success()
+        .stdout(predicate::str::diff("a \"quoted\" string"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn eval_raw_non_string() {
+    assert_cmd(&["--raw", "--expr", "1"])
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "Cannot print an int as a raw string",
+        ));
+}
+
+#[test]
+fn eval_pretty() {
+    assert_cmd(&["--pretty", "--expr", "{ b = [ 1 2 ]; a = \"x\"; }"])
+        .success()
+        .stdout(predicate::str::diff(
+            "{\n  a = \"x\";\n  b = [\n    1\n    2\n  ];\n}\n",
+        ))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn eval_show_trace() {
+    assert_cmd(&[
+        "--show-trace",
+        "--expr",
+        "let\n  f = x: assert x; x;\nin f false",
+    ])
+    .failure()
+    .stdout(predicate::str::is_empty())
+    .stderr(predicate::str::contains(
+        "       … from call site\n         at «string»:3:4:\n",
+    ))
+    .stderr(predicate::str::contains(
+        "       … while evaluating the assertion\n         at «string»:2:10:\n",
+    ))
+    .stderr(predicate::str::contains("2|   f = x: assert x; x;\n"))
+    .stderr(predicate::str::contains("error: Assertion 'x' failed"));
+}
+
+#[test]
+fn eval_show_trace_non_ascii() {
+    assert_cmd(&[
+        "--show-trace",
+        "--expr",
+        "{ a = \"😀\"; b = assert false; 1; }.b",
+    ])
+    .failure()
+    .stderr(predicate::str::contains(
+        "       … while evaluating the assertion\n         at «string»:1:17:\n",
+    ))
+    .stderr(predicate::str::contains(
+        "1| { a = \"😀\"; b = assert false; 1; }.b\n",
+    ))
+    .stderr(predicate::str::contains(" |                ^\n"));
+}
+
+#[test]
+fn eval_without_show_trace() {
+    assert_cmd(&["--expr", "let\n  f = x: assert x; x;\nin f false"])
+        .failure()
+        .stderr(predicate::str::contains("from call site").not());
+}
+
+#[test]
+fn eval_color_always() {
+    assert_cmd(&["--color=always", "--expr", "assert false; 1"])
+        .failure()
+        .stderr(predicate::str::contains(
+            "Assertion '\u{1b}[1;35mfalse\u{1b}[0m' failed",
+        ));
+}
+
+#[test]
+fn eval_color_never() {
+    assert_cmd(&["--color=never", "--expr", "assert false; 1"])
+        .failure()
+        .stderr(predicate::str::diff("error: Assertion 'false' failed\n"));
+}
+
+#[test]
+fn eval_color_auto_no_color() {
+    Command::cargo_bin("rix")
+        .unwrap()
+        .args(["eval", "--expr", "assert false; 1"])
+        .env("NO_COLOR", "1")
+        .env("XDG_CACHE_HOME", test_cache_home())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\u{1b}[").not());
+}
+
+#[test]
+fn eval_error_format_json() {
+    let output = assert_cmd(&["--error-format=json", "--expr", "{ a = 1 }"])
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(
+        error,
+        serde_json::json!({
+            "kind": "ParseError",
+            "fields": {
+                "file": "«string»",
+                "line": 1,
+                "column": 9,
+                "message": "syntax error, unexpected '}', expecting ';'",
+            },
+            "message": [{
+                "type": "plain",
+                "text": "syntax error, unexpected '}', expecting ';'",
+            }],
+            "location": { "file": "«string»", "line": 1, "column": 9 },
+            "trace": [],
+        })
+    );
+}
+
+#[test]
+fn eval_error_format_json_file() {
+    let output = assert_cmd(&[
+        "--error-format=json",
+        "-f",
+        "src/tests/import_tests/invalid-syntax.nix",
+    ])
+    .failure()
+    .get_output()
+    .stderr
+    .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert!(error["location"]["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("src/tests/import_tests/invalid-syntax.nix"));
+}
+
+#[test]
+fn eval_error_format_json_eval_error() {
+    let output = assert_cmd(&["--error-format=json", "--expr", "\n  assert false; 1"])
+        .failure()
+        .get_output()
+        .stderr
+        .clone();
+    let error: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(error["kind"], "AssertionFailed");
+    assert_eq!(error["fields"], serde_json::json!({ "condition": "false" }));
+    assert_eq!(
+        error["message"][1],
+        serde_json::json!({ "type": "highlighted", "text": "false" })
+    );
+    assert_eq!(
+        error["location"],
+        serde_json::json!({ "file": "«string»", "line": 2, "column": 3 })
+    );
+}
+
+#[test]
+fn eval_exit_codes() {
+    assert_cmd(&["--expr", "assert false; 1"]).code(1);
+    assert_cmd(&[]).code(2);
+    assert_cmd(&["--expr", "{}", "a.\"b"]).code(2);
+    assert_cmd(&["--expr", "{ a = 1 }"]).code(3);
+    assert_cmd(&["--file", "does-not-exist.nix"]).code(4);
+    assert_cmd(&["--expr", "import ./does-not-exist.nix"]).code(4);
+    assert_cmd(&["--expr", "builtins.derivation {}"]).code(5);
+}
+
+#[test]
+fn rix_without_subcommand() {
+    Command::cargo_bin("rix")
+        .unwrap()
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage: rix"));
+}
+
+#[test]
+fn eval_restrict_eval() {
+    assert_cmd(&[
+        "--restrict-eval",
+        "--expr",
+        "builtins.pathExists ./Cargo.toml",
+    ])
+    .code(4)
+    .stderr(predicate::str::contains("is forbidden in restricted mode"));
+    assert_cmd(&[
+        "--restrict-eval",
+        "-I",
+        ".",
+        "--expr",
+        "builtins.pathExists ./Cargo.toml",
+    ])
+    .success()
+    .stdout(predicate::str::diff("true\n"));
+}
+
+#[test]
+fn eval_pure_eval() {
+    assert_cmd(&[
+        "--pure-eval",
+        "-I",
+        "nixpkgs=./src",
+        "--expr",
+        "builtins.pathExists ./Cargo.toml",
+    ])
+    .code(4)
+    .stderr(predicate::str::contains(
+        "is forbidden in pure evaluation mode",
+    ));
+}
+
+#[test]
+fn eval_system() {
+    assert_cmd(&[
+        "--system",
+        "aarch64-darwin",
+        "--expr",
+        "builtins.currentSystem",
+    ])
+    .success()
+    .stdout(predicate::str::diff("\"aarch64-darwin\"\n"));
+}
+
+#[test]
+fn eval_pure_eval_get_env() {
+    assert_cmd(&["--pure-eval", "--expr", r#"builtins.getEnv "PATH""#])
+        .success()
+        .stdout(predicate::str::diff("\"\"\n"));
+}
+
+#[test]
+fn eval_cache() {
+    let cache_home = std::env::temp_dir().join(format!("rix-eval-cache-{}", std::process::id()));
+    for _ in 0..2 {
+        assert_cmd_with_cache_home(&["--expr", "1 + 2"], &cache_home)
+            .success()
+            .stdout(predicate::str::diff("3\n"))
+            .stderr(predicate::str::is_empty());
+    }
+    let cache_entries: Vec<_> = std::fs::read_dir(cache_home.join("rix"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert!(cache_entries
+        .iter()
+        .any(|path| path.extension().is_some_and(|ext| ext == "js")));
+    assert!(cache_entries
+        .iter()
+        .any(|path| path.extension().is_some_and(|ext| ext == "v8cache")));
+    std::fs::remove_dir_all(cache_home).unwrap();
+}
+
+#[test]
+fn eval_no_eval_cache() {
+    let cache_home = std::env::temp_dir().join(format!("rix-no-eval-cache-{}", std::process::id()));
+    assert_cmd_with_cache_home(&["--no-eval-cache", "--expr", "1 + 2"], &cache_home)
+        .success()
+        .stdout(predicate::str::diff("3\n"));
+    assert!(!cache_home.exists());
+}
+
+fn assert_cmd_with_cache_home(
+    eval_args: &[&str],
+    cache_home: &std::path::Path,
+) -> assert_cmd::assert::Assert {
+    Command::cargo_bin("rix")
+        .unwrap()
+        .arg("eval")
+        .args(eval_args)
+        .env("XDG_CACHE_HOME", cache_home)
+        .assert()
+}
+
 fn assert_cmd(eval_args: &[&str]) -> assert_cmd::assert::Assert {
-    let mut rix_args = vec!["eval"];
-    rix_args.extend_from_slice(eval_args);
-    return Command::cargo_bin("rix").unwrap().args(rix_args).assert();
+    assert_cmd_with_cache_home(eval_args, &test_cache_home())
+}
+
+/// Keeps the evaluation cache of the tests out of the user's cache directory.
+fn test_cache_home() -> std::path::PathBuf {
+    std::env::temp_dir().join("rix-cmd-tests-cache")
 }
@@ -13,12 +13,57 @@ fn transpile_help() {
 fn transpile_bool_expr() {
     assert_cmd(&["--expr", "1.0"])
         .success()
-        .stdout(predicate::str::contains(
-            "export default (ctx) => new n.NixFloat(1.0);\n",
+        .stdout(predicate::str::diff(
+            "import * as n from \"nixjs-rt\";\nexport default (ctx) => new n.NixFloat(1.0);\n",
         ))
         .stderr(predicate::str::is_empty());
 }
 
+#[test]
+fn transpile_file() {
+    assert_cmd(&["src/tests/import_tests/basic.nix"])
+        .success()
+        .stdout(predicate::str::contains("new n.NixString(`imported!`)"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn transpile_with_source_map() {
+    let out_dir = std::env::temp_dir().join(format!("rix-transpile-{}", std::process::id()));
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let js_path = out_dir.join("basic.js");
+    assert_cmd(&[
+        "src/tests/import_tests/basic.nix",
+        "--output",
+        js_path.to_str().unwrap(),
+        "--source-map",
+    ])
+    .success()
+    .stdout(predicate::str::is_empty())
+    .stderr(predicate::str::is_empty());
+
+    let js_source = std::fs::read_to_string(&js_path).unwrap();
+    assert!(js_source.ends_with("//# sourceMappingURL=basic.js.map\n"));
+    let source_map = std::fs::read_to_string(out_dir.join("basic.js.map")).unwrap();
+    assert!(source_map.contains(r#""version":3"#));
+    assert!(source_map.contains(r#""file":"basic.js""#));
+    assert!(source_map.contains("basic.nix"));
+    assert!(source_map.contains(r#""mappings":";wBAAA,"#));
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn transpile_source_map_requires_output() {
+    assert_cmd(&["--expr", "1", "--source-map"]).failure();
+}
+
+#[test]
+fn transpile_missing_expression() {
+    assert_cmd(&[]).code(2).stderr(predicate::str::contains(
+        "You must provide a single expression to transpile.",
+    ));
+}
+
 fn assert_cmd(eval_args: &[&str]) -> assert_cmd::assert::Assert {
     let mut rix_args = vec!["transpile"];
     rix_args.extend_from_slice(eval_args);